@@ -0,0 +1,390 @@
+//!
+//! # Cassette
+//!
+//! Records HTTP interactions of [K8Client](super::K8Client) to file and replays them later,
+//! so responses of real API server can be decoded in tests without cluster.
+//!
+//! Interactions are kept as JSON in order they were sent. Host is not recorded,
+//! so cassette can be replayed against any client.
+//! Bearer tokens and values of `data` and `stringData` of secrets are redacted,
+//! other request and response bodies are stored verbatim.
+//! Cassette is saved when body of each response is finished or dropped, so watch is
+//! saved when it ends rather than as events arrive.
+//!
+//! ```ignore
+//! // record against cluster
+//! let client = K8Client::try_default()?.with_cassette(Cassette::record("pods.json"));
+//! client.retrieve_items::<PodSpec, _>("default").await?;
+//!
+//! // replay without cluster
+//! let client = K8Client::replay("pods.json")?;
+//! client.retrieve_items::<PodSpec, _>("default").await?;
+//! ```
+//!
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex, PoisonError};
+
+use anyhow::{anyhow, Context, Result};
+use futures_util::stream::StreamExt;
+use hyper::body::to_bytes;
+use hyper::header::{HeaderMap, AUTHORIZATION};
+use hyper::{Body, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::{debug, error};
+
+use crate::meta_client::K8Error;
+
+use super::HyperClient;
+
+const REDACTED: &str = "<redacted>";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct RecordedRequest {
+    method: String,
+    /// path and query without host
+    uri: String,
+    headers: BTreeMap<String, String>,
+    #[serde(with = "text")]
+    body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    fn new(request: &Request<Body>, body: &[u8]) -> Self {
+        Self {
+            method: request.method().to_string(),
+            uri: path_and_query(request),
+            headers: headers(request.headers()),
+            body: body.to_vec(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct RecordedResponse {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    #[serde(with = "text")]
+    body: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+impl Interaction {
+    /// copy with secret data redacted from bodies
+    fn redacted(&self) -> Self {
+        let mut interaction = self.clone();
+        redact_body(&mut interaction.request.body);
+        redact_body(&mut interaction.response.body);
+        interaction
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Mode {
+    Record,
+    Replay,
+}
+
+/// recorded interactions, see [module](self) documentation
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    /// recorded interactions, or interactions not yet replayed
+    interactions: StdMutex<VecDeque<Interaction>>,
+}
+
+impl Cassette {
+    /// record interactions to file, existing file is replaced
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: Mode::Record,
+            interactions: StdMutex::new(VecDeque::new()),
+        }
+    }
+
+    /// replay interactions recorded in file.
+    /// each request is answered by first recorded interaction with same method and uri which is not yet replayed
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let contents =
+            fs::read(&path).with_context(|| format!("reading cassette {}", path.display()))?;
+        let interactions = serde_json::from_slice(&contents)
+            .with_context(|| format!("parsing cassette {}", path.display()))?;
+        Ok(Self {
+            path,
+            mode: Mode::Replay,
+            interactions: StdMutex::new(interactions),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.mode == Mode::Replay
+    }
+
+    pub(crate) async fn send(
+        self: Arc<Self>,
+        client: &HyperClient,
+        request: Request<Body>,
+    ) -> Result<Response<Body>> {
+        match self.mode {
+            Mode::Record => self.record_interaction(client, request).await,
+            Mode::Replay => self.replay_interaction(request),
+        }
+    }
+
+    /// send request and record response body as it is streamed
+    async fn record_interaction(
+        self: Arc<Self>,
+        client: &HyperClient,
+        request: Request<Body>,
+    ) -> Result<Response<Body>> {
+        let (parts, body) = request.into_parts();
+        let body = to_bytes(body).await?;
+        let request = Request::from_parts(parts, Body::from(body.clone()));
+        let recorded_request = RecordedRequest::new(&request, &body);
+
+        let response = client.request(request).await.map_err(K8Error::transport)?;
+        let (parts, body) = response.into_parts();
+
+        let index = {
+            let mut interactions = self.lock();
+            interactions.push_back(Interaction {
+                request: recorded_request,
+                response: RecordedResponse {
+                    status: parts.status.as_u16(),
+                    headers: headers(&parts.headers),
+                    body: vec![],
+                },
+            });
+            interactions.len() - 1
+        };
+
+        let saver = SaveOnDrop(self.clone());
+        let body = body.map(move |chunk| {
+            if let Ok(chunk) = &chunk {
+                saver.0.lock()[index].response.body.extend_from_slice(chunk);
+            }
+            chunk
+        });
+        Ok(Response::from_parts(parts, Body::wrap_stream(body)))
+    }
+
+    fn replay_interaction(&self, request: Request<Body>) -> Result<Response<Body>> {
+        let method = request.method().to_string();
+        let uri = path_and_query(&request);
+
+        let interaction = {
+            let mut interactions = self.lock();
+            let position = interactions
+                .iter()
+                .position(|interaction| {
+                    interaction.request.method == method && interaction.request.uri == uri
+                })
+                .ok_or_else(|| {
+                    anyhow!(
+                        "no recorded interaction for {} {} in cassette {}",
+                        method,
+                        uri,
+                        self.path.display()
+                    )
+                })?;
+            interactions.remove(position).expect("position is valid")
+        };
+        debug!(%method, %uri, status = interaction.response.status, "replaying");
+
+        let mut response =
+            Response::builder().status(StatusCode::from_u16(interaction.response.status)?);
+        for (name, value) in &interaction.response.headers {
+            response = response.header(name, value);
+        }
+        Ok(response.body(Body::from(interaction.response.body))?)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<Interaction>> {
+        self.interactions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn save(&self) -> Result<()> {
+        let interactions: Vec<Interaction> =
+            self.lock().iter().map(Interaction::redacted).collect();
+        let contents = serde_json::to_vec_pretty(&interactions)?;
+        fs::write(&self.path, contents)
+            .with_context(|| format!("writing cassette {}", self.path.display()))
+    }
+}
+
+/// saves cassette when response body is finished or dropped
+struct SaveOnDrop(Arc<Cassette>);
+
+impl Drop for SaveOnDrop {
+    fn drop(&mut self) {
+        if let Err(err) = self.0.save() {
+            error!(%err, "error saving cassette");
+        }
+    }
+}
+
+fn path_and_query<B>(request: &Request<B>) -> String {
+    request
+        .uri()
+        .path_and_query()
+        .map(|path| path.to_string())
+        .unwrap_or_default()
+}
+
+/// headers with credentials redacted
+fn headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if name == AUTHORIZATION {
+                match value.to_str() {
+                    Ok(value) if value.starts_with("Bearer ") => format!("Bearer {REDACTED}"),
+                    _ => REDACTED.to_owned(),
+                }
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+/// redact data of secrets in JSON body, or in each line of watch body.
+/// body is only re-encoded if it contains secret
+fn redact_body(body: &mut Vec<u8>) {
+    let mut redacted = false;
+    let lines: Vec<Vec<u8>> = body
+        .split(|byte| *byte == b'\n')
+        .map(|line| {
+            if let Ok(mut value) = serde_json::from_slice::<Value>(line) {
+                if redact_secrets(&mut value) {
+                    redacted = true;
+                    return serde_json::to_vec(&value).unwrap_or_default();
+                }
+            }
+            line.to_vec()
+        })
+        .collect();
+    if redacted {
+        *body = lines.join(&b'\n');
+    }
+}
+
+/// redact secret, list of secrets or watch event of secret. returns true if anything was redacted
+fn redact_secrets(value: &mut Value) -> bool {
+    let Value::Object(object) = value else {
+        return false;
+    };
+    match object.get("kind").and_then(Value::as_str) {
+        Some("Secret") => redact_secret_data(object),
+        Some("SecretList") => match object.get_mut("items") {
+            Some(Value::Array(items)) => {
+                items
+                    .iter_mut()
+                    .filter_map(Value::as_object_mut)
+                    .map(redact_secret_data)
+                    .filter(|redacted| *redacted)
+                    .count()
+                    > 0
+            }
+            _ => false,
+        },
+        _ => object.get_mut("object").is_some_and(redact_secrets),
+    }
+}
+
+fn redact_secret_data(secret: &mut Map<String, Value>) -> bool {
+    let mut redacted = false;
+    for field in ["data", "stringData"] {
+        if let Some(Value::Object(data)) = secret.get_mut(field) {
+            for value in data.values_mut() {
+                *value = REDACTED.into();
+                redacted = true;
+            }
+        }
+    }
+    redacted
+}
+
+/// body as text, API server only returns JSON and plain text
+mod text {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(body: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&String::from_utf8_lossy(body))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        String::deserialize(deserializer).map(String::into_bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+    use hyper::{Body, Request};
+
+    use super::{redact_body, RecordedRequest};
+
+    #[test]
+    fn test_token_redacted() {
+        let request = Request::get("https://cluster:6443/api/v1/pods?limit=1")
+            .header(AUTHORIZATION, "Bearer secret-token")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::empty())
+            .expect("request");
+
+        let recorded = RecordedRequest::new(&request, b"");
+        assert_eq!(recorded.uri, "/api/v1/pods?limit=1");
+        assert_eq!(recorded.headers["authorization"], "Bearer <redacted>");
+        assert_eq!(recorded.headers["content-type"], "application/json");
+
+        let json = serde_json::to_string(&recorded).expect("json");
+        assert!(!json.contains("secret-token"));
+    }
+
+    #[test]
+    fn test_secret_redacted() {
+        let mut body = br#"{"kind":"Secret","apiVersion":"v1","metadata":{"name":"db"},"data":{"password":"c2VjcmV0"},"stringData":{"user":"admin"}}"#.to_vec();
+        redact_body(&mut body);
+        let secret: serde_json::Value = serde_json::from_slice(&body).expect("json");
+        assert_eq!(secret["data"]["password"], "<redacted>");
+        assert_eq!(secret["stringData"]["user"], "<redacted>");
+        assert_eq!(secret["metadata"]["name"], "db");
+
+        let mut body = br#"{"kind":"SecretList","apiVersion":"v1","metadata":{},"items":[{"metadata":{"name":"db"},"data":{"password":"c2VjcmV0"}}]}"#.to_vec();
+        redact_body(&mut body);
+        assert!(!String::from_utf8_lossy(&body).contains("c2VjcmV0"));
+
+        let mut body = b"{\"type\":\"ADDED\",\"object\":{\"kind\":\"Secret\",\"data\":{\"token\":\"dG9rZW4=\"}}}\n".to_vec();
+        redact_body(&mut body);
+        let body = String::from_utf8_lossy(&body);
+        assert!(!body.contains("dG9rZW4="));
+        assert!(body.ends_with('\n'));
+
+        // other bodies are kept as they are
+        let pods = br#"{"kind": "PodList", "items": []}"#.to_vec();
+        let mut body = pods.clone();
+        redact_body(&mut body);
+        assert_eq!(body, pods);
+    }
+}
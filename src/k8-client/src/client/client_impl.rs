@@ -1,17 +1,19 @@
 use std::fmt::Debug;
 use std::fmt::Display;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Instant;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use bytes::Buf;
+use futures_util::future::ready;
+use futures_util::future::Future;
 use futures_util::future::FutureExt;
-use futures_util::stream::empty;
+use futures_util::stream::once;
 use futures_util::stream::BoxStream;
 use futures_util::stream::Stream;
 use futures_util::stream::StreamExt;
 use futures_util::stream::TryStreamExt;
-use http::StatusCode;
 use http::header::InvalidHeaderValue;
 use hyper::body::aggregate;
 use hyper::body::Bytes;
@@ -21,6 +23,7 @@ use hyper::header::AUTHORIZATION;
 use hyper::header::CONTENT_TYPE;
 use hyper::Body;
 use hyper::Request;
+use hyper::Response;
 use hyper::Uri;
 use serde::{Serialize, Deserialize};
 use serde::de::DeserializeOwned;
@@ -29,16 +32,29 @@ use tracing::debug;
 use tracing::error;
 use tracing::trace;
 
-use k8_types::{UpdatedK8Obj, MetaStatus};
+use k8_types::{UpdatedK8Obj, MetaStatus, StatusEnum};
 use k8_config::K8Config;
 use k8_types::{InputK8Obj, K8List, K8Meta, K8Obj, DeleteStatus, K8Watch, Spec, UpdateK8ObjStatus};
 use k8_types::options::{ListOptions, DeleteOptions};
 
 use crate::uri::{item_uri, items_uri};
-use crate::meta_client::{ListArg, MetadataClient, NameSpace, PatchMergeType, TokenStreamResult};
+use crate::meta_client::{
+    apply_patch, ApplyConflict, ApplyOptions, K8Error, K8ErrorExt, ListArg, MetadataClient,
+    NameSpace, PatchMergeType, TokenStreamResult,
+};
 
 use super::wstream::WatchStream;
-use super::{HyperClient, HyperConfigBuilder, ListStream, LogStream};
+use super::discovery::Discovery;
+use super::rate_limit::trace_flow_control;
+use super::{HyperClient, HyperConfigBuilder, ListStream, LogStream, RateLimiter, RetryPolicy};
+#[cfg(feature = "cassette")]
+use super::cassette::Cassette;
+#[cfg(any(feature = "cassette", feature = "fake_server"))]
+use super::HyperClientBuilder;
+
+/// host of client replaying cassette, it is never connected
+#[cfg(feature = "cassette")]
+const REPLAY_HOST: &str = "https://replay.invalid";
 
 /// K8 Cluster accessible thru API
 #[derive(Debug)]
@@ -46,6 +62,14 @@ pub struct K8Client {
     client: HyperClient,
     host: String,
     token: Option<String>,
+    retry: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// discovery fetches many group versions at once, so it has its own limiter like in client-go
+    pub(super) discovery_rate_limiter: Option<Arc<RateLimiter>>,
+    /// cached discovery and when it was fetched, see [K8Client::discovery]
+    pub(super) discovery: StdMutex<Option<(Instant, Arc<Discovery>)>>,
+    #[cfg(feature = "cassette")]
+    cassette: Option<Arc<Cassette>>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Default, Clone)]
@@ -69,6 +93,11 @@ impl K8Client {
         Self::new(config)
     }
 
+    /// client for cluster in config. requests are rate limited to
+    /// [DEFAULT_QPS](super::rate_limit::DEFAULT_QPS) with [DEFAULT_BURST](super::rate_limit::DEFAULT_BURST),
+    /// discovery to [DISCOVERY_QPS](super::rate_limit::DISCOVERY_QPS) with
+    /// [DISCOVERY_BURST](super::rate_limit::DISCOVERY_BURST).
+    /// use [K8Client::without_rate_limit] to disable it
     pub fn new(config: K8Config) -> Result<Self> {
         let helper = HyperConfigBuilder::new(config)?;
         let host = helper.host();
@@ -79,6 +108,73 @@ impl K8Client {
             client,
             host,
             token,
+            retry: RetryPolicy::default(),
+            rate_limiter: Some(Arc::new(RateLimiter::default())),
+            discovery_rate_limiter: Some(Arc::new(RateLimiter::discovery())),
+            discovery: StdMutex::new(None),
+            #[cfg(feature = "cassette")]
+            cassette: None,
+        })
+    }
+
+    /// client of fake api server, which is served over plain `http`
+    #[cfg(feature = "fake_server")]
+    pub(crate) fn new_with_http(config: K8Config) -> Result<Self> {
+        use crate::cert::ConfigBuilder;
+
+        let mut client = Self::new(config)?;
+        client.client = HyperClientBuilder::new().build_with_http()?;
+        Ok(client)
+    }
+
+    /// limit rate of requests sent to cluster, default is
+    /// [DEFAULT_QPS](super::rate_limit::DEFAULT_QPS) with [DEFAULT_BURST](super::rate_limit::DEFAULT_BURST)
+    pub fn with_rate_limit(mut self, qps: f64, burst: u32) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(qps, burst)));
+        self
+    }
+
+    /// limit rate of discovery requests, default is
+    /// [DISCOVERY_QPS](super::rate_limit::DISCOVERY_QPS) with [DISCOVERY_BURST](super::rate_limit::DISCOVERY_BURST)
+    pub fn with_discovery_rate_limit(mut self, qps: f64, burst: u32) -> Self {
+        self.discovery_rate_limiter = Some(Arc::new(RateLimiter::new(qps, burst)));
+        self
+    }
+
+    /// send requests, including discovery, without client side throttling
+    pub fn without_rate_limit(mut self) -> Self {
+        self.rate_limiter = None;
+        self.discovery_rate_limiter = None;
+        self
+    }
+
+    /// retry policy for idempotent requests, see [RetryPolicy]
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// record or replay interactions using cassette
+    #[cfg(feature = "cassette")]
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(Arc::new(cassette));
+        self
+    }
+
+    /// client which replays interactions recorded in cassette file without connecting to cluster
+    #[cfg(feature = "cassette")]
+    pub fn replay(path: impl Into<std::path::PathBuf>) -> Result<Self> {
+        use crate::cert::ConfigBuilder;
+
+        Ok(Self {
+            client: HyperClientBuilder::new().build()?,
+            host: REPLAY_HOST.to_owned(),
+            token: None,
+            retry: RetryPolicy::default(),
+            rate_limiter: Some(Arc::new(RateLimiter::default())),
+            discovery_rate_limiter: Some(Arc::new(RateLimiter::discovery())),
+            discovery: StdMutex::new(None),
+            cassette: Some(Arc::new(Cassette::replay(path)?)),
         })
     }
 
@@ -91,11 +187,14 @@ impl K8Client {
         Ok(info)
     }
 
-    fn hostname(&self) -> &str {
+    pub(crate) fn hostname(&self) -> &str {
         &self.host
     }
 
-    fn finish_request<B>(&self, request: &mut Request<B>) -> Result<(), InvalidHeaderValue>
+    pub(super) fn finish_request<B>(
+        &self,
+        request: &mut Request<B>,
+    ) -> Result<(), InvalidHeaderValue>
     where
         B: Into<Body>,
     {
@@ -108,8 +207,61 @@ impl K8Client {
         Ok(())
     }
 
+    /// send request to cluster, thru cassette if there is one.
+    /// each attempt waits for rate limiter, request is retried according to retry policy
+    pub(super) fn send(
+        &self,
+        request: Request<Body>,
+    ) -> impl Future<Output = Result<Response<Body>>> + Send + 'static {
+        self.send_with_limiter(request, self.rate_limiter.clone())
+    }
+
+    /// same as [K8Client::send] but waits for given rate limiter, None sends without throttling
+    fn send_with_limiter(
+        &self,
+        request: Request<Body>,
+        rate_limiter: Option<Arc<RateLimiter>>,
+    ) -> impl Future<Output = Result<Response<Body>>> + Send + 'static {
+        let client = self.client.clone();
+        #[cfg(feature = "cassette")]
+        let cassette = self.cassette.clone();
+        let retry = self.retry.clone();
+        let send_once = move |request: Request<Body>| {
+            let client = client.clone();
+            #[cfg(feature = "cassette")]
+            let cassette = cassette.clone();
+            let rate_limiter = rate_limiter.clone();
+            async move {
+                if let Some(rate_limiter) = rate_limiter {
+                    rate_limiter.acquire().await;
+                }
+                #[cfg(feature = "cassette")]
+                if let Some(cassette) = cassette {
+                    return cassette.send(&client, request).await;
+                }
+                let response = client.request(request).await.map_err(K8Error::transport)?;
+                trace_flow_control(&response);
+                Ok(response)
+            }
+        };
+        async move { retry.send(request, send_once).await }
+    }
+
     /// handle request. this is async function
-    async fn handle_request<T>(&self, mut request: Request<Body>) -> Result<T>
+    pub(super) async fn handle_request<T>(&self, request: Request<Body>) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.handle_request_with_limiter(request, self.rate_limiter.clone())
+            .await
+    }
+
+    /// same as [K8Client::handle_request] but waits for given rate limiter
+    pub(super) async fn handle_request_with_limiter<T>(
+        &self,
+        mut request: Request<Body>,
+        rate_limiter: Option<Arc<RateLimiter>>,
+    ) -> Result<T>
     where
         T: DeserializeOwned,
     {
@@ -120,7 +272,7 @@ impl K8Client {
         trace!("request url: {}", request.uri());
         trace!("request body: {:?}", request.body());
 
-        let resp = self.client.request(request).await?;
+        let resp = self.send_with_limiter(request, rate_limiter).await?;
 
         let status = resp.status();
 
@@ -132,28 +284,31 @@ impl K8Client {
             serde_json::from_slice(&buffer).map_err(|err| {
                 error!("json error: {}", err);
                 error!("source: {}", String::from_utf8_lossy(&buffer));
-                err.into()
+                K8Error::Decode(err).into()
             })
         } else {
             trace!(%status, "error response received");
-            let mut reader = (aggregate(resp).await?).reader();
-            let mut buffer = Vec::new();
-            reader.read_to_end(&mut buffer).map_err(|err| {
-                error!("unable to read error response: {}", err);
-                err
-            })?;
-            trace!("error response: {}", String::from_utf8_lossy(&buffer));
-            let api_status: MetaStatus = serde_json::from_slice(&buffer).map_err(|err| {
-                error!("json error: {}", err);
-                err
-            })?;
-            Err(api_status.into())
+            Err(error_response(resp).await)
         }
     }
 
     /// return stream of chunks, chunk is a bytes that are stream thru http channel
-    #[allow(clippy::useless_conversion)]
+    /// stream ends if there is error
     fn stream_of_chunks(&self, uri: Uri) -> impl Stream<Item = Bytes> {
+        self.try_stream_of_chunks(uri).filter_map(|chunk| {
+            ready(match chunk {
+                Ok(chunk) => Some(chunk),
+                Err(err) => {
+                    error!("error getting streaming: {}", err);
+                    None
+                }
+            })
+        })
+    }
+
+    /// same as stream_of_chunks but error in establishing stream is returned as last item
+    #[allow(clippy::useless_conversion)]
+    pub(super) fn try_stream_of_chunks(&self, uri: Uri) -> impl Stream<Item = Result<Bytes>> {
         debug!("streaming: {}", uri);
 
         let request = http::Request::get(uri)
@@ -163,64 +318,59 @@ impl K8Client {
                 Ok(req)
             });
 
-        let http_client = self.client.clone();
+        let response = request.map(|request| self.send(request));
 
         let ft = async move {
-            let request = match request {
-                Ok(req) => req,
+            let response = match response {
+                Ok(response) => response,
                 Err(err) => {
                     error!("error building request: {}", err);
-                    return empty().right_stream();
+                    return once(ready(Err(err.into()))).right_stream();
                 }
             };
 
-            match http_client.request(request).await {
+            match response.await {
                 Ok(response) => {
-                    trace!("res status: {}", response.status());
+                    let status = response.status();
+                    trace!("res status: {}", status);
                     trace!("res header: {:#?}", response.headers());
-                    WatchStream::new(response.into_body().map_err(|err| err.into())).left_stream()
-                }
-                Err(err) => {
-                    error!("error getting streaming: {}", err);
-                    empty().right_stream()
+                    if status.is_success() {
+                        WatchStream::new(response.into_body().map_err(|err| err.into()))
+                            .map(Ok)
+                            .left_stream()
+                    } else {
+                        once(ready(Err(error_response(response).await))).right_stream()
+                    }
                 }
+                Err(err) => once(ready(Err(err))).right_stream(),
             }
         };
 
         ft.flatten_stream()
     }
 
-    /// return get stream of uri
-    fn stream<S>(&self, uri: Uri) -> impl Stream<Item = TokenStreamResult<S>> + '_
+    /// return get stream of uri, stream ends if it can't be established
+    pub(crate) fn stream<S>(&self, uri: Uri) -> impl Stream<Item = TokenStreamResult<S>> + '_
     where
         K8Watch<S>: DeserializeOwned,
         S: Spec + 'static,
         S::Status: 'static,
         S::Header: 'static,
     {
-        self.stream_of_chunks(uri).map(move |chunk| {
-            trace!(
-                "decoding raw stream : {}",
-                String::from_utf8_lossy(&chunk).to_string()
-            );
+        self.stream_of_chunks(uri)
+            .map(move |chunk| Ok(vec![decode_watch_chunk(&chunk)]))
+    }
 
-            let result: Result<K8Watch<S>, serde_json::Error> = serde_json::from_slice(&chunk)
-                .map_err(|err| {
-                    error!("parsing error, chunk_len: {}, error: {}", chunk.len(), err);
-                    error!(
-                        "error raw stream {}",
-                        String::from_utf8_lossy(&chunk).to_string()
-                    );
-                    err
-                });
-            Ok(vec![match result {
-                Ok(obj) => {
-                    trace!("de serialized: {:#?}", obj);
-                    Ok(obj)
-                }
-                Err(err) => Err(err.into()),
-            }])
-        })
+    /// same as stream but error in establishing stream is returned as last item
+    pub(super) fn try_stream<S>(&self, uri: Uri) -> impl Stream<Item = TokenStreamResult<S>> + '_
+    where
+        K8Watch<S>: DeserializeOwned,
+        S: Spec + 'static,
+        S::Status: 'static,
+        S::Header: 'static,
+    {
+        self.try_stream_of_chunks(uri)
+            .map(move |chunk| Ok(vec![decode_watch_chunk(&chunk?)]))
     }
 
     pub async fn retrieve_items_inner<S, N>(
@@ -241,38 +391,6 @@ impl K8Client {
         Ok(items)
     }
 
-    /// replace existing object.
-    /// object must exist
-    pub async fn replace_item<S>(&self, value: UpdatedK8Obj<S>) -> Result<K8Obj<S>>
-    where
-        S: Spec,
-    {
-        let metadata = &value.metadata;
-        debug!( name = %metadata.name,"replace item");
-        trace!("replace {:#?}", value);
-        let uri = item_uri::<S>(
-            self.hostname(),
-            metadata.name(),
-            metadata.namespace(),
-            None,
-            None,
-        )?;
-
-        let bytes = serde_json::to_vec(&value)?;
-
-        trace!(
-            "replace uri: {}, raw: {}",
-            uri,
-            String::from_utf8_lossy(&bytes).to_string()
-        );
-
-        let request = Request::put(uri)
-            .header(CONTENT_TYPE, "application/json")
-            .body(bytes.into())?;
-
-        self.handle_request(request).await
-    }
-
     pub async fn retrieve_log(
         &self,
         namespace: &str,
@@ -315,20 +433,8 @@ impl MetadataClient for K8Client {
 
         match result {
             Ok(item) => Ok(Some(item)),
-            Err(err) => {
-                if let Some(MetaStatus {
-                    code: Some(code), ..
-                }) = err.downcast_ref()
-                {
-                    if *code == StatusCode::NOT_FOUND.as_u16() {
-                        Ok(None)
-                    } else {
-                        Err(err)
-                    }
-                } else {
-                    Err(err)
-                }
-            }
+            Err(err) if err.is_not_found() => Ok(None),
+            Err(err) => Err(err),
         }
     }
 
@@ -341,12 +447,8 @@ impl MetadataClient for K8Client {
         S: Spec,
         N: Into<NameSpace> + Send + Sync,
     {
-        let list_option = option.map(|opt| ListOptions {
-            field_selector: opt.field_selector,
-            label_selector: opt.label_selector,
-            ..Default::default()
-        });
-        self.retrieve_items_inner(namespace, list_option).await
+        self.retrieve_items_inner(namespace, option.map(ListOptions::from))
+            .await
     }
 
     fn retrieve_items_in_chunks<'a, S, N>(
@@ -435,6 +537,80 @@ impl MetadataClient for K8Client {
         self.handle_request(request).await
     }
 
+    async fn replace_item<S>(&self, value: UpdatedK8Obj<S>) -> Result<K8Obj<S>>
+    where
+        S: Spec,
+    {
+        let metadata = &value.metadata;
+        debug!( name = %metadata.name,"replace item");
+        trace!("replace {:#?}", value);
+        let uri = item_uri::<S>(
+            self.hostname(),
+            metadata.name(),
+            metadata.namespace(),
+            None,
+            None,
+        )?;
+
+        let bytes = serde_json::to_vec(&value)?;
+
+        trace!(
+            "replace uri: {}, raw: {}",
+            uri,
+            String::from_utf8_lossy(&bytes).to_string()
+        );
+
+        let request = Request::put(uri)
+            .header(CONTENT_TYPE, "application/json")
+            .body(bytes.into())?;
+
+        self.handle_request(request).await
+    }
+
+    async fn server_side_apply<S>(
+        &self,
+        value: InputK8Obj<S>,
+        options: ApplyOptions,
+    ) -> Result<K8Obj<S>>
+    where
+        S: Spec,
+    {
+        let metadata = &value.metadata;
+        debug!(%metadata, field_manager = ?options.field_manager, "server side apply");
+        let params = serde_qs::to_string(&options)?;
+        let uri = item_uri::<S>(
+            self.hostname(),
+            metadata.name(),
+            metadata.namespace(),
+            None,
+            Some(&params),
+        )?;
+
+        let bytes = serde_json::to_vec(&apply_patch(&value)?)?;
+
+        trace!(
+            "apply uri: {}, raw: {}",
+            uri,
+            String::from_utf8_lossy(&bytes).to_string()
+        );
+
+        let request = Request::patch(uri)
+            .header(ACCEPT, "application/json")
+            .header(CONTENT_TYPE, PatchMergeType::Apply(options).content_type())
+            .body(bytes.into())?;
+
+        self.handle_request(request).await.map_err(|err| {
+            match err
+                .k8_error()
+                .and_then(K8Error::status)
+                .and_then(ApplyConflict::from_status)
+            {
+                Some(conflict) => conflict.into(),
+                None => err,
+            }
+        })
+    }
+
     /// update status
     async fn update_status<S>(&self, value: &UpdateK8ObjStatus<S>) -> Result<K8Obj<S>>
     where
@@ -476,12 +652,13 @@ impl MetadataClient for K8Client {
     {
         debug!(%metadata, "patching");
         trace!("patch json value: {:#?}", patch);
+        let params = patch_params(&merge_type)?;
         let uri = item_uri::<S>(
             self.hostname(),
             metadata.name(),
             metadata.namespace(),
             None,
-            None,
+            params.as_deref(),
         )?;
 
         let bytes = serde_json::to_vec(&patch)?;
@@ -528,13 +705,7 @@ impl MetadataClient for K8Client {
     {
         tracing::info!(%metadata, "patching subresource");
         tracing::info!("patch json value: {:#?}", patch);
-        let params = match &merge_type {
-            PatchMergeType::Apply(params) => {
-                let params = serde_qs::to_string(&params)?;
-                Some(params)
-            }
-            _ => None,
-        };
+        let params = patch_params(&merge_type)?;
         let uri = item_uri::<S>(
             self.hostname(),
             metadata.name(),
@@ -559,7 +730,8 @@ impl MetadataClient for K8Client {
         self.handle_request(request).await
     }
 
-    /// stream items since resource versions
+    /// stream items since resource versions.
+    /// stream ends if watch can't be established, [K8Client::watch_stream_resilient] reports the error
     fn watch_stream_since<S, N>(
         &self,
         namespace: N,
@@ -581,3 +753,80 @@ impl MetadataClient for K8Client {
         self.stream(uri).boxed()
     }
 }
+
+/// decode single event of watch stream, watch error is returned as K8Error
+fn decode_watch_chunk<S>(chunk: &Bytes) -> Result<K8Watch<S>>
+where
+    K8Watch<S>: DeserializeOwned,
+    S: Spec,
+{
+    trace!(
+        "decoding raw stream : {}",
+        String::from_utf8_lossy(chunk).to_string()
+    );
+
+    let result: Result<K8Watch<S>, serde_json::Error> =
+        serde_json::from_slice(chunk).map_err(|err| {
+            error!("parsing error, chunk_len: {}, error: {}", chunk.len(), err);
+            error!(
+                "error raw stream {}",
+                String::from_utf8_lossy(chunk).to_string()
+            );
+            err
+        });
+    match result {
+        Ok(K8Watch::ERROR(status)) => {
+            debug!(%status, "watch error");
+            Err(K8Error::from(status).into_anyhow())
+        }
+        Ok(obj) => {
+            trace!("de serialized: {:#?}", obj);
+            Ok(obj)
+        }
+        Err(err) => Err(K8Error::Decode(err).into()),
+    }
+}
+
+/// decode error response from api server as K8Error.
+/// responses which are not Status, for example from proxy, are classified by HTTP status
+pub(super) async fn error_response(resp: Response<Body>) -> anyhow::Error {
+    use std::io::Read;
+
+    let code = resp.status();
+    let read = async {
+        let mut reader = (aggregate(resp).await.map_err(K8Error::transport)?).reader();
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer).map_err(|err| {
+            error!("unable to read error response: {}", err);
+            K8Error::transport(err)
+        })?;
+        trace!("error response: {}", String::from_utf8_lossy(&buffer));
+        let api_status = serde_json::from_slice::<MetaStatus>(&buffer).unwrap_or_else(|err| {
+            debug!("error response is not status: {}", err);
+            MetaStatus {
+                api_version: "v1".to_owned(),
+                code: Some(code.as_u16()),
+                details: None,
+                kind: "Status".to_owned(),
+                message: Some(String::from_utf8_lossy(&buffer).trim().to_owned()),
+                reason: code
+                    .canonical_reason()
+                    .map(|reason| reason.replace(' ', "")),
+                status: StatusEnum::FAILURE,
+            }
+        });
+        Ok::<_, K8Error>(K8Error::from(api_status))
+    };
+
+    match read.await {
+        Ok(err) | Err(err) => err.into_anyhow(),
+    }
+}
+
+/// query parameters for patch, only apply has parameters
+pub(super) fn patch_params(merge_type: &PatchMergeType) -> Result<Option<String>> {
+    match merge_type {
+        PatchMergeType::Apply(options) => Ok(Some(serde_qs::to_string(options)?)),
+        _ => Ok(None),
+    }
+}
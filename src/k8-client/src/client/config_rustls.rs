@@ -1,4 +1,4 @@
-use std::io::{Error as IoError, ErrorKind, Result as IoResult};
+use std::io::{Error as IoError, Result as IoResult};
 use std::net::ToSocketAddrs;
 use std::path::Path;
 use std::pin::Pin;
@@ -31,7 +31,14 @@ pub type HyperClient = Client<TlsHyperConnector, Body>;
 
 pub type HyperConfigBuilder = ClientConfigBuilder<HyperClientBuilder>;
 
-pub struct HyperTlsStream(DefaultClientTlsStream);
+#[allow(clippy::large_enum_variant)]
+pub enum HyperTlsStream {
+    Tls(DefaultClientTlsStream),
+    /// plain connection to `http` server, only used by client of fake api server
+    /// since credentials would be sent in cleartext
+    #[cfg(feature = "fake_server")]
+    Plain(TcpStream),
+}
 
 impl Connection for HyperTlsStream {
     fn connected(&self) -> Connected {
@@ -45,7 +52,12 @@ impl AsyncRead for HyperTlsStream {
         cx: &mut Context<'_>,
         buf: &mut ReadBuf<'_>,
     ) -> Poll<IoResult<()>> {
-        match Pin::new(&mut self.0).poll_read(cx, buf.initialize_unfilled())? {
+        let result = match &mut *self {
+            Self::Tls(stream) => Pin::new(stream).poll_read(cx, buf.initialize_unfilled()),
+            #[cfg(feature = "fake_server")]
+            Self::Plain(stream) => Pin::new(stream).poll_read(cx, buf.initialize_unfilled()),
+        };
+        match result? {
             Poll::Ready(bytes_read) => {
                 buf.advance(bytes_read);
                 Poll::Ready(Ok(()))
@@ -61,25 +73,50 @@ impl AsyncWrite for HyperTlsStream {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<IoResult<usize>> {
-        Pin::new(&mut self.0).poll_write(cx, buf)
+        match &mut *self {
+            Self::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "fake_server")]
+            Self::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
-        Pin::new(&mut self.0).poll_flush(cx)
+        match &mut *self {
+            Self::Tls(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "fake_server")]
+            Self::Plain(stream) => Pin::new(stream).poll_flush(cx),
+        }
     }
 
     fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
-        Pin::new(&mut self.0).poll_close(cx)
+        match &mut *self {
+            Self::Tls(stream) => Pin::new(stream).poll_close(cx),
+            #[cfg(feature = "fake_server")]
+            Self::Plain(stream) => Pin::new(stream).poll_close(cx),
+        }
     }
 }
 
 /// hyper connector that uses fluvio TLS
 #[derive(Clone)]
-pub struct TlsHyperConnector(Arc<TlsConnector>);
+pub struct TlsHyperConnector {
+    tls: Arc<TlsConnector>,
+    /// connect to `http` servers, only set for fake api server
+    allow_http: bool,
+}
 
 impl TlsHyperConnector {
     fn new(connector: TlsConnector) -> Self {
-        Self(Arc::new(connector))
+        Self {
+            tls: Arc::new(connector),
+            allow_http: false,
+        }
+    }
+
+    fn client(self) -> HyperClient {
+        Client::builder()
+            .executor(FluvioHyperExecutor)
+            .build::<_, Body>(self)
     }
 }
 
@@ -94,7 +131,8 @@ impl Service<Uri> for TlsHyperConnector {
     }
 
     fn call(&mut self, uri: Uri) -> Self::Future {
-        let connector = self.0.clone();
+        let connector = self.tls.clone();
+        let allow_http = self.allow_http;
 
         Box::pin(async move {
             let host = match uri.host() {
@@ -102,30 +140,32 @@ impl Service<Uri> for TlsHyperConnector {
                 None => return Err(anyhow!("no host")),
             };
 
-            match uri.scheme_str() {
-                Some("http") => Err(anyhow!("http not supported")),
-                Some("https") => {
-                    let socket_addr = {
-                        let host = host.to_string();
-                        let port = uri.port_u16().unwrap_or(443);
-                        match (host.as_str(), port).to_socket_addrs()?.next() {
-                            Some(addr) => addr,
-                            None => return Err(anyhow!("host resolution: {} failed", host)),
-                        }
-                    };
-                    debug!("socket address to: {}", socket_addr);
-                    let tcp_stream = TcpStream::connect(&socket_addr).await?;
-
-                    let stream = connector
-                        .connect(host.try_into()?, tcp_stream)
-                        .await
-                        .map_err(|err| {
-                            IoError::new(ErrorKind::Other, format!("tls handshake: {}", err))
-                        })?;
-                    Ok(HyperTlsStream(stream))
+            let tls = match uri.scheme_str() {
+                Some("http") if allow_http => false,
+                Some("http") => return Err(anyhow!("http not supported")),
+                Some("https") => true,
+                scheme => return Err(anyhow!("{:?}", scheme)),
+            };
+            let socket_addr = {
+                let host = host.to_string();
+                let port = uri.port_u16().unwrap_or(if tls { 443 } else { 80 });
+                match (host.as_str(), port).to_socket_addrs()?.next() {
+                    Some(addr) => addr,
+                    None => return Err(anyhow!("host resolution: {} failed", host)),
                 }
-                scheme => Err(anyhow!("{:?}", scheme)),
+            };
+            debug!("socket address to: {}", socket_addr);
+            let tcp_stream = TcpStream::connect(&socket_addr).await?;
+            #[cfg(feature = "fake_server")]
+            if !tls {
+                return Ok(HyperTlsStream::Plain(tcp_stream));
             }
+
+            let stream = connector
+                .connect(host.try_into()?, tcp_stream)
+                .await
+                .map_err(|err| IoError::other(format!("tls handshake: {}", err)))?;
+            Ok(HyperTlsStream::Tls(stream))
         })
     }
 }
@@ -207,6 +247,21 @@ impl From<ConnectorBuilderWithConfig> for ConnectorBuilderStages {
 //#[derive(Default)]
 pub struct HyperClientBuilder(ConnectorBuilderStages);
 
+impl HyperClientBuilder {
+    fn connector(self) -> Result<TlsHyperConnector> {
+        let connector = self.0.build()?;
+        Ok(TlsHyperConnector::new(connector))
+    }
+
+    /// client which also connects to plain `http` servers, only used by fake api server
+    #[cfg(feature = "fake_server")]
+    pub(crate) fn build_with_http(self) -> Result<HyperClient> {
+        let mut connector = self.connector()?;
+        connector.allow_http = true;
+        Ok(connector.client())
+    }
+}
+
 impl ConfigBuilder for HyperClientBuilder {
     type Client = HyperClient;
 
@@ -215,11 +270,7 @@ impl ConfigBuilder for HyperClientBuilder {
     }
 
     fn build(self) -> Result<Self::Client> {
-        let connector = self.0.build()?;
-
-        Ok(Client::builder()
-            .executor(FluvioHyperExecutor)
-            .build::<_, Body>(TlsHyperConnector::new(connector)))
+        Ok(self.connector()?.client())
     }
 
     fn load_ca_certificate(self, ca_path: impl AsRef<Path>) -> Result<Self> {
@@ -0,0 +1,429 @@
+//!
+//! # Discovery
+//!
+//! Groups, versions and resources served by API server, as used by `kubectl api-resources`.
+//! Discovery is fetched by [K8Client::discovery] and cached for [DISCOVERY_TTL],
+//! use [K8Client::refresh_discovery] to fetch it sooner, for example after CRD is installed.
+//! Group versions are fetched concurrently, throttled by discovery rate limiter of client,
+//! see [K8Client::with_discovery_rate_limit].
+//!
+//! ```ignore
+//! let discovery = client.discovery().await?;
+//! let deployments = discovery.resolve("deploy").expect("deployments");
+//! assert_eq!(deployments.resource.plural, "deployments");
+//! ```
+//!
+use std::sync::{Arc, PoisonError};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use futures_util::future::join_all;
+use futures_util::stream::{self, StreamExt};
+use hyper::{Body, Request};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use k8_types::ApiResource;
+
+use super::K8Client;
+
+/// how long discovery is cached
+pub const DISCOVERY_TTL: Duration = Duration::from_secs(600);
+
+/// number of groups fetched at same time
+const DISCOVERY_CONCURRENCY: usize = 16;
+
+/// response of `/api`, versions of core group
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiVersions {
+    pub kind: String,
+    pub versions: Vec<String>,
+}
+
+/// response of `/apis`
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiGroupList {
+    pub kind: String,
+    pub api_version: String,
+    pub groups: Vec<ApiGroup>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiGroup {
+    pub name: String,
+    pub versions: Vec<GroupVersionForDiscovery>,
+    pub preferred_version: Option<GroupVersionForDiscovery>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GroupVersionForDiscovery {
+    /// `group/version`
+    pub group_version: String,
+    pub version: String,
+}
+
+/// response of `/api/{version}` and `/apis/{group}/{version}`
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiResourceList {
+    pub kind: String,
+    pub api_version: String,
+    pub group_version: String,
+    pub resources: Vec<ApiResourceInfo>,
+}
+
+/// resource served in group version
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiResourceInfo {
+    /// plural name, subresources are named `<plural>/<subresource>`
+    pub name: String,
+    pub singular_name: String,
+    pub namespaced: bool,
+    pub kind: String,
+    pub verbs: Vec<String>,
+    pub short_names: Vec<String>,
+    pub categories: Vec<String>,
+}
+
+impl ApiResourceInfo {
+    pub fn is_subresource(&self) -> bool {
+        self.name.contains('/')
+    }
+
+    /// name is kind, plural, singular or short name of resource, ignoring case
+    fn is_named(&self, name: &str) -> bool {
+        self.kind.eq_ignore_ascii_case(name)
+            || self.name.eq_ignore_ascii_case(name)
+            || self.singular_name.eq_ignore_ascii_case(name)
+            || self
+                .short_names
+                .iter()
+                .any(|short_name| short_name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscoveredVersion {
+    pub version: String,
+    pub resources: Vec<ApiResourceInfo>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscoveredGroup {
+    /// empty for core group
+    pub name: String,
+    pub preferred_version: String,
+    pub versions: Vec<DiscoveredVersion>,
+}
+
+impl DiscoveredGroup {
+    pub fn version(&self, version: &str) -> Option<&DiscoveredVersion> {
+        self.versions
+            .iter()
+            .find(|discovered| discovered.version == version)
+    }
+
+    /// versions with preferred version first
+    fn preferred_versions(&self) -> impl Iterator<Item = &DiscoveredVersion> {
+        self.version(&self.preferred_version).into_iter().chain(
+            self.versions
+                .iter()
+                .filter(|version| version.version != self.preferred_version),
+        )
+    }
+}
+
+/// resource resolved from discovery
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DiscoveredResource {
+    pub resource: ApiResource,
+    pub verbs: Vec<String>,
+    pub short_names: Vec<String>,
+}
+
+impl DiscoveredResource {
+    fn new(group: &str, version: &str, info: &ApiResourceInfo) -> Self {
+        Self {
+            resource: ApiResource {
+                group: group.to_owned(),
+                version: version.to_owned(),
+                kind: info.kind.clone(),
+                plural: info.name.clone(),
+                namespaced: info.namespaced,
+            },
+            verbs: info.verbs.clone(),
+            short_names: info.short_names.clone(),
+        }
+    }
+
+    pub fn supports(&self, verb: &str) -> bool {
+        self.verbs.iter().any(|supported| supported == verb)
+    }
+}
+
+/// groups served by API server, core group is first
+#[derive(Debug, Clone, Default)]
+pub struct Discovery {
+    groups: Vec<DiscoveredGroup>,
+}
+
+impl Discovery {
+    pub fn new(groups: Vec<DiscoveredGroup>) -> Self {
+        Self { groups }
+    }
+
+    pub fn groups(&self) -> &[DiscoveredGroup] {
+        &self.groups
+    }
+
+    /// group by name, empty name or `core` is core group
+    pub fn group(&self, name: &str) -> Option<&DiscoveredGroup> {
+        let name = if name == "core" { "" } else { name };
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    pub fn preferred_version(&self, group: &str) -> Option<&str> {
+        self.group(group)
+            .map(|group| group.preferred_version.as_str())
+    }
+
+    /// resource of kind in group version, for example to check that CRD is installed
+    pub fn resource(&self, group: &str, version: &str, kind: &str) -> Option<DiscoveredResource> {
+        let discovered = self.group(group)?;
+        discovered
+            .version(version)?
+            .resources
+            .iter()
+            .find(|info| !info.is_subresource() && info.kind == kind)
+            .map(|info| DiscoveredResource::new(&discovered.name, version, info))
+    }
+
+    /// resolve kind, plural, singular or short name to resource in preferred version.
+    /// name can be qualified by group as `deployments.apps`
+    pub fn resolve(&self, name: &str) -> Option<DiscoveredResource> {
+        if let Some(found) = self.resolve_in(self.groups.iter(), name) {
+            return Some(found);
+        }
+        let (name, group) = name.split_once('.')?;
+        self.resolve_in(self.group(group).into_iter(), name)
+    }
+
+    fn resolve_in<'a>(
+        &self,
+        groups: impl Iterator<Item = &'a DiscoveredGroup>,
+        name: &str,
+    ) -> Option<DiscoveredResource> {
+        groups.into_iter().find_map(|group| {
+            group.preferred_versions().find_map(|version| {
+                version
+                    .resources
+                    .iter()
+                    .find(|info| !info.is_subresource() && info.is_named(name))
+                    .map(|info| DiscoveredResource::new(&group.name, &version.version, info))
+            })
+        })
+    }
+}
+
+impl K8Client {
+    /// discovery of API server, it is fetched on first use and cached for [DISCOVERY_TTL]
+    pub async fn discovery(&self) -> Result<Arc<Discovery>> {
+        let cached = self
+            .discovery
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        match cached {
+            Some((fetched, discovery)) if fetched.elapsed() < DISCOVERY_TTL => Ok(discovery),
+            _ => self.refresh_discovery().await,
+        }
+    }
+
+    /// fetch discovery again, for example after CRD is installed
+    pub async fn refresh_discovery(&self) -> Result<Arc<Discovery>> {
+        let fetched = Instant::now();
+        let core: ApiVersions = self.get_discovery("api").await?;
+        let group_list: ApiGroupList = self.get_discovery("apis").await?;
+
+        let core = core
+            .versions
+            .first()
+            .cloned()
+            .map(|preferred_version| (String::new(), preferred_version, core.versions.clone()));
+        let groups = group_list.groups.into_iter().map(|group| {
+            let versions: Vec<String> = group
+                .versions
+                .iter()
+                .map(|version| version.version.clone())
+                .collect();
+            let preferred_version = group
+                .preferred_version
+                .map(|version| version.version)
+                .or_else(|| versions.first().cloned())
+                .unwrap_or_default();
+            (group.name, preferred_version, versions)
+        });
+
+        let groups = stream::iter(core.into_iter().chain(groups))
+            .map(|(name, preferred_version, versions)| {
+                self.discover_group(name, preferred_version, versions)
+            })
+            .buffered(DISCOVERY_CONCURRENCY)
+            .collect()
+            .await;
+
+        let discovery = Arc::new(Discovery::new(groups));
+        *self
+            .discovery
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some((fetched, discovery.clone()));
+        Ok(discovery)
+    }
+
+    /// resources of group versions. versions which are not available, such as of aggregated API
+    /// which is down, are skipped
+    async fn discover_group(
+        &self,
+        name: String,
+        preferred_version: String,
+        versions: Vec<String>,
+    ) -> DiscoveredGroup {
+        let discovered = join_all(versions.into_iter().map(|version| async {
+            let path = if name.is_empty() {
+                format!("api/{version}")
+            } else {
+                format!("apis/{name}/{version}")
+            };
+            match self.get_discovery::<ApiResourceList>(&path).await {
+                Ok(list) => Some(DiscoveredVersion {
+                    version,
+                    resources: list.resources,
+                }),
+                Err(err) => {
+                    warn!(%path, %err, "unable to discover resources");
+                    None
+                }
+            }
+        }))
+        .await;
+        DiscoveredGroup {
+            name,
+            preferred_version,
+            versions: discovered.into_iter().flatten().collect(),
+        }
+    }
+
+    async fn get_discovery<T>(&self, path: &str) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let uri = format!("{}/{}", self.hostname(), path);
+        debug!(%uri, "discovery");
+        self.handle_request_with_limiter(
+            Request::get(uri).body(Body::empty())?,
+            self.discovery_rate_limiter.clone(),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{ApiGroupList, ApiResourceList, DiscoveredGroup, DiscoveredVersion, Discovery};
+
+    fn discovery() -> Discovery {
+        let core: ApiResourceList = serde_json::from_str(
+            r#"{"kind":"APIResourceList","groupVersion":"v1","resources":[
+                {"name":"pods","singularName":"pod","namespaced":true,"kind":"Pod","verbs":["create","delete","get","list","patch","update","watch"],"shortNames":["po"],"categories":["all"]},
+                {"name":"pods/log","singularName":"","namespaced":true,"kind":"Pod","verbs":["get"]},
+                {"name":"namespaces","singularName":"namespace","namespaced":false,"kind":"Namespace","verbs":["create","delete","get","list","patch","update","watch"],"shortNames":["ns"]}
+            ]}"#,
+        )
+        .expect("core");
+        let groups: ApiGroupList = serde_json::from_str(
+            r#"{"kind":"APIGroupList","apiVersion":"v1","groups":[
+                {"name":"autoscaling","versions":[{"groupVersion":"autoscaling/v2","version":"v2"},{"groupVersion":"autoscaling/v1","version":"v1"}],"preferredVersion":{"groupVersion":"autoscaling/v2","version":"v2"}}
+            ]}"#,
+        )
+        .expect("groups");
+        let hpa = |version: &str| {
+            let list: ApiResourceList = serde_json::from_str(&format!(
+                r#"{{"kind":"APIResourceList","groupVersion":"autoscaling/{version}","resources":[
+                    {{"name":"horizontalpodautoscalers","singularName":"horizontalpodautoscaler","namespaced":true,"kind":"HorizontalPodAutoscaler","verbs":["get","list"],"shortNames":["hpa"]}}
+                ]}}"#
+            ))
+            .expect("hpa");
+            DiscoveredVersion {
+                version: version.to_owned(),
+                resources: list.resources,
+            }
+        };
+
+        let autoscaling = &groups.groups[0];
+        Discovery::new(vec![
+            DiscoveredGroup {
+                name: String::new(),
+                preferred_version: "v1".to_owned(),
+                versions: vec![DiscoveredVersion {
+                    version: "v1".to_owned(),
+                    resources: core.resources,
+                }],
+            },
+            DiscoveredGroup {
+                name: autoscaling.name.clone(),
+                preferred_version: autoscaling
+                    .preferred_version
+                    .as_ref()
+                    .expect("preferred")
+                    .version
+                    .clone(),
+                versions: vec![hpa("v1"), hpa("v2")],
+            },
+        ])
+    }
+
+    #[test]
+    fn test_resolve() {
+        let discovery = discovery();
+
+        let pods = discovery.resolve("po").expect("pods");
+        assert_eq!(pods.resource.plural, "pods");
+        assert_eq!(pods.resource.api_version(), "v1");
+        assert!(pods.resource.namespaced);
+        assert!(pods.supports("watch"));
+
+        let namespaces = discovery.resolve("Namespace").expect("namespaces");
+        assert!(!namespaces.resource.namespaced);
+
+        let hpa = discovery.resolve("hpa").expect("hpa");
+        assert_eq!(hpa.resource.api_version(), "autoscaling/v2");
+        assert!(!hpa.supports("create"));
+        assert_eq!(
+            discovery.resolve("horizontalpodautoscalers.autoscaling"),
+            Some(hpa)
+        );
+
+        assert!(discovery.resolve("pods/log").is_none());
+        assert!(discovery.resolve("deployments").is_none());
+    }
+
+    #[test]
+    fn test_resource() {
+        let discovery = discovery();
+        assert_eq!(discovery.preferred_version("autoscaling"), Some("v2"));
+        assert_eq!(discovery.preferred_version("core"), Some("v1"));
+        assert!(discovery
+            .resource("autoscaling", "v1", "HorizontalPodAutoscaler")
+            .is_some());
+        assert!(discovery
+            .resource("autoscaling", "v2beta1", "HorizontalPodAutoscaler")
+            .is_none());
+        assert!(discovery.resource("", "v1", "Pod").is_some());
+    }
+}
@@ -0,0 +1,190 @@
+//!
+//! # Dynamic API
+//!
+//! Operations on objects of resources described at runtime by [ApiResource],
+//! for example resources discovered from API server or found in user's manifest.
+//!
+use anyhow::Result;
+use futures_util::stream::{BoxStream, StreamExt};
+use hyper::header::{ACCEPT, CONTENT_TYPE};
+use hyper::{Body, Request};
+use serde_json::Value;
+use tracing::{debug, error, trace};
+
+use k8_types::options::{DeleteOptions, ListOptions};
+use k8_types::{ApiResource, DynamicList, DynamicObject, DynamicWatch};
+
+use crate::meta_client::{K8Error, K8ErrorExt, ListArg, NameSpace, PatchMergeType};
+use crate::uri::{resource_item_uri, resource_prefix_uri};
+
+use super::client_impl::patch_params;
+use super::K8Client;
+
+/// same as [TokenStreamResult](crate::meta_client::TokenStreamResult) for dynamic objects
+pub type DynamicStreamResult = Result<Vec<Result<DynamicWatch>>>;
+
+impl K8Client {
+    /// retrieve object, None if it doesn't exist
+    pub async fn retrieve_dynamic_item(
+        &self,
+        resource: &ApiResource,
+        name: &str,
+        namespace: &str,
+    ) -> Result<Option<DynamicObject>> {
+        let uri = resource_item_uri(resource, self.hostname(), name, namespace, None)?;
+        debug!("{}: retrieving dynamic item: {}", resource.kind, uri);
+
+        match self
+            .handle_request(Request::get(uri).body(Body::empty())?)
+            .await
+        {
+            Ok(item) => Ok(Some(item)),
+            Err(err) if err.is_not_found() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn retrieve_dynamic_items<N>(
+        &self,
+        resource: &ApiResource,
+        namespace: N,
+        option: Option<ListArg>,
+    ) -> Result<DynamicList>
+    where
+        N: Into<NameSpace>,
+    {
+        let list_option = option.map(ListOptions::from);
+        let uri = resource_prefix_uri(resource, self.hostname(), namespace, list_option);
+        debug!("{}: retrieving dynamic items: {}", resource.kind, uri);
+        self.handle_request(Request::get(uri).body(Body::empty())?)
+            .await
+    }
+
+    /// create object in namespace of its metadata
+    pub async fn create_dynamic_item(
+        &self,
+        resource: &ApiResource,
+        value: &DynamicObject,
+    ) -> Result<DynamicObject> {
+        let uri = resource_prefix_uri(
+            resource,
+            self.hostname(),
+            value.metadata.namespace.as_str(),
+            None,
+        );
+        debug!("creating '{}'", uri);
+
+        let bytes = serde_json::to_vec(value)?;
+        trace!(
+            "create {} raw: {}",
+            resource.kind,
+            String::from_utf8_lossy(&bytes)
+        );
+
+        let request = Request::post(uri)
+            .header(CONTENT_TYPE, "application/json")
+            .body(bytes.into())?;
+        self.handle_request(request).await
+    }
+
+    /// patch object, server side apply is done with [PatchMergeType::Apply]
+    pub async fn patch_dynamic_item(
+        &self,
+        resource: &ApiResource,
+        name: &str,
+        namespace: &str,
+        patch: &Value,
+        merge_type: PatchMergeType,
+    ) -> Result<DynamicObject> {
+        let params = patch_params(&merge_type)?;
+        let uri = resource_item_uri(
+            resource,
+            self.hostname(),
+            name,
+            namespace,
+            params.as_deref(),
+        )?;
+
+        let bytes = serde_json::to_vec(patch)?;
+        debug!(
+            "patch dynamic uri: {}, raw: {}",
+            uri,
+            String::from_utf8_lossy(&bytes)
+        );
+
+        let request = Request::patch(uri)
+            .header(ACCEPT, "application/json")
+            .header(CONTENT_TYPE, merge_type.content_type())
+            .body(bytes.into())?;
+        self.handle_request(request).await
+    }
+
+    /// delete object. if object is still being deleted, such as in foreground deletion, it is returned
+    pub async fn delete_dynamic_item(
+        &self,
+        resource: &ApiResource,
+        name: &str,
+        namespace: &str,
+        option: Option<DeleteOptions>,
+    ) -> Result<Option<DynamicObject>> {
+        let uri = resource_item_uri(resource, self.hostname(), name, namespace, None)?;
+        debug!("{}: delete dynamic item on url: {}", resource.kind, uri);
+
+        let body = match option {
+            Some(option) => serde_json::to_vec(&option)?.into(),
+            None => Body::empty(),
+        };
+        let request = Request::delete(uri)
+            .header(ACCEPT, "application/json")
+            .body(body)?;
+        let value: Value = self.handle_request(request).await?;
+        if value.get("kind").and_then(Value::as_str) == Some("Status") {
+            Ok(None)
+        } else {
+            Ok(Some(serde_json::from_value(value)?))
+        }
+    }
+
+    /// stream changes since resource version
+    pub fn watch_dynamic_stream_since<N>(
+        &self,
+        resource: &ApiResource,
+        namespace: N,
+        resource_version: Option<String>,
+    ) -> BoxStream<'_, DynamicStreamResult>
+    where
+        N: Into<NameSpace>,
+    {
+        let opt = ListOptions {
+            watch: Some(true),
+            resource_version,
+            timeout_seconds: Some(3600),
+            ..Default::default()
+        };
+        let uri = resource_prefix_uri(resource, self.hostname(), namespace, Some(opt));
+        let uri = match uri.parse() {
+            Ok(uri) => uri,
+            Err(err) => {
+                return futures_util::stream::once(async move { Err(anyhow::Error::from(err)) })
+                    .boxed()
+            }
+        };
+
+        self.try_stream_of_chunks(uri)
+            .map(|chunk| {
+                let chunk = chunk?;
+                Ok(vec![match serde_json::from_slice(&chunk) {
+                    Ok(DynamicWatch::ERROR(status)) => {
+                        debug!(%status, "watch error");
+                        Err(K8Error::from(status).into_anyhow())
+                    }
+                    Ok(event) => Ok(event),
+                    Err(err) => {
+                        error!("parsing error, chunk_len: {}, error: {}", chunk.len(), err);
+                        Err(K8Error::Decode(err).into())
+                    }
+                }])
+            })
+            .boxed()
+    }
+}
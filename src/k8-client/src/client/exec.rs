@@ -0,0 +1,385 @@
+//!
+//! # Exec
+//!
+//! Running command in container of pod, or attaching to its main process, over WebSocket.
+//! stdout and stderr are readers, stdin is writer and exit status is reported by server
+//! on error channel when process ends.
+//!
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use anyhow::{anyhow, Result};
+use async_channel::{bounded, Receiver, Sender, WeakSender};
+use bytes::Bytes;
+use futures_util::io::{AsyncRead, AsyncWrite};
+use futures_util::stream::{BoxStream, IntoAsyncRead, SplitStream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, trace};
+
+use fluvio_future::task::spawn;
+use k8_types::core::pod::PodSpec;
+use k8_types::{StatusDetails, StatusEnum};
+
+use crate::uri::item_uri;
+
+use super::ws::{
+    next_binary, write_messages, ChannelWriter, WsStream, CHANNEL_CAPACITY, CLOSE, ERROR, RESIZE,
+    STDERR, STDIN, STDOUT, V4_CHANNEL, V5_CHANNEL,
+};
+use super::K8Client;
+
+/// exit code reported by server when process exits with non zero code
+const NON_ZERO_EXIT_CODE: &str = "NonZeroExitCode";
+const EXIT_CODE: &str = "ExitCode";
+
+/// query of `exec` and `attach` subresources, command is added separately
+/// since it is repeated for each argument
+#[derive(Serialize)]
+struct ExecQuery<'a> {
+    container: &'a str,
+    stdin: bool,
+    stdout: bool,
+    stderr: bool,
+    tty: bool,
+}
+
+#[derive(Serialize)]
+struct CommandArg<'a> {
+    command: &'a str,
+}
+
+/// status sent on error channel
+#[derive(Deserialize, Debug)]
+struct StatusMessage {
+    status: StatusEnum,
+    message: Option<String>,
+    reason: Option<String>,
+    details: Option<StatusDetails>,
+}
+
+/// exit status of process
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExecStatus {
+    /// None if process could not be run, such as when command doesn't exist
+    pub exit_code: Option<i32>,
+    pub message: Option<String>,
+}
+
+impl ExecStatus {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+impl From<StatusMessage> for ExecStatus {
+    fn from(status: StatusMessage) -> Self {
+        let exit_code = match status.status {
+            StatusEnum::SUCCESS => Some(0),
+            StatusEnum::FAILURE if status.reason.as_deref() == Some(NON_ZERO_EXIT_CODE) => status
+                .details
+                .iter()
+                .flat_map(|details| details.causes.iter())
+                .find(|cause| cause.reason.as_deref() == Some(EXIT_CODE))
+                .and_then(|cause| cause.message.as_deref()?.parse().ok()),
+            StatusEnum::FAILURE => None,
+        };
+        Self {
+            exit_code,
+            message: status.message,
+        }
+    }
+}
+
+/// terminal size sent on resize channel
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct TerminalSize {
+    width: u16,
+    height: u16,
+}
+
+/// output of process, ends when process exits.
+/// output is buffered up to limit, process is blocked while output is not read
+pub struct ExecReader {
+    reader: IntoAsyncRead<BoxStream<'static, io::Result<Bytes>>>,
+    /// keeps connection open while output is read
+    _outgoing: Sender<Message>,
+}
+
+impl ExecReader {
+    fn new(receiver: Receiver<Bytes>, outgoing: Sender<Message>) -> Self {
+        Self {
+            reader: receiver.map(Ok).boxed().into_async_read(),
+            _outgoing: outgoing,
+        }
+    }
+}
+
+impl AsyncRead for ExecReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.reader).poll_read(cx, buf)
+    }
+}
+
+/// input of process. closing writer closes stdin of process, which is only supported
+/// by `v5.channel.k8s.io` protocol
+pub struct ExecWriter {
+    writer: ChannelWriter,
+    close_supported: bool,
+    closed: bool,
+}
+
+impl AsyncWrite for ExecWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.writer.poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.writer.poll_pending(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.writer.poll_pending(cx))?;
+        if self.close_supported && !self.closed {
+            self.closed = true;
+            // connection may be already closed if process has exited
+            let _ = self.writer.start_send(Message::Binary(vec![CLOSE, STDIN]));
+        }
+        let _ = ready!(self.writer.poll_pending(cx));
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// process running in container, connection is closed when process exits
+/// or when process, its stdin, stdout and stderr are dropped
+pub struct AttachedProcess {
+    stdin: Option<ExecWriter>,
+    stdout: Option<ExecReader>,
+    stderr: Option<ExecReader>,
+    outgoing: Sender<Message>,
+    status: Receiver<ExecStatus>,
+}
+
+impl AttachedProcess {
+    fn new(stream: WsStream, protocol: &str, stdin: bool, stderr: bool) -> Self {
+        let (sink, stream) = stream.split();
+        let (outgoing, outgoing_receiver) = bounded(CHANNEL_CAPACITY);
+        let (stdout_sender, stdout) = bounded(CHANNEL_CAPACITY);
+        let (stderr_sender, stderr_receiver) = bounded(CHANNEL_CAPACITY);
+        let (status_sender, status) = bounded(1);
+
+        spawn(write_messages(sink, outgoing_receiver));
+        spawn(read_messages(
+            stream,
+            stdout_sender,
+            stderr_sender,
+            status_sender,
+            outgoing.downgrade(),
+        ));
+
+        Self {
+            stdin: stdin.then(|| ExecWriter {
+                writer: ChannelWriter::new(outgoing.clone(), STDIN),
+                close_supported: protocol == V5_CHANNEL,
+                closed: false,
+            }),
+            stdout: Some(ExecReader::new(stdout, outgoing.clone())),
+            stderr: stderr.then(|| ExecReader::new(stderr_receiver, outgoing.clone())),
+            outgoing,
+            status,
+        }
+    }
+
+    /// take stdin of process, None if already taken or if stdin was not requested
+    pub fn stdin(&mut self) -> Option<ExecWriter> {
+        self.stdin.take()
+    }
+
+    /// take stdout of process, None if already taken
+    pub fn stdout(&mut self) -> Option<ExecReader> {
+        self.stdout.take()
+    }
+
+    /// take stderr of process, None if already taken or if process has terminal,
+    /// in which case stderr is merged with stdout
+    pub fn stderr(&mut self) -> Option<ExecReader> {
+        self.stderr.take()
+    }
+
+    /// change size of terminal of process
+    pub async fn resize(&self, width: u16, height: u16) -> Result<()> {
+        let mut data = vec![RESIZE];
+        serde_json::to_writer(&mut data, &TerminalSize { width, height })?;
+        self.outgoing
+            .send(Message::Binary(data))
+            .await
+            .map_err(|_| anyhow!("connection to process is closed"))
+    }
+
+    /// wait for process to exit. stdout and stderr must be read or dropped,
+    /// otherwise process may be blocked writing output
+    pub async fn status(&self) -> Result<ExecStatus> {
+        self.status
+            .recv()
+            .await
+            .map_err(|_| anyhow!("connection closed before exit status was received"))
+    }
+}
+
+/// demultiplex messages from server until connection is closed.
+/// outgoing is weak so connection is closed when all handles of process are dropped
+async fn read_messages(
+    mut stream: SplitStream<WsStream>,
+    stdout: Sender<Bytes>,
+    stderr: Sender<Bytes>,
+    status: Sender<ExecStatus>,
+    outgoing: WeakSender<Message>,
+) {
+    while let Some(data) = next_binary(&mut stream).await {
+        // server sends empty message on each channel when connected
+        let Some((&channel, payload)) = data.split_first() else {
+            continue;
+        };
+        if payload.is_empty() {
+            continue;
+        }
+        match channel {
+            // output is dropped if reader is dropped
+            STDOUT => {
+                let _ = stdout.send(Bytes::copy_from_slice(payload)).await;
+            }
+            STDERR => {
+                let _ = stderr.send(Bytes::copy_from_slice(payload)).await;
+            }
+            ERROR => match serde_json::from_slice::<StatusMessage>(payload) {
+                Ok(message) => {
+                    let _ = status.send(message.into()).await;
+                }
+                Err(err) => debug!(%err, "invalid status: {}", String::from_utf8_lossy(payload)),
+            },
+            channel => trace!(channel, "ignoring message on unknown channel"),
+        }
+    }
+    if let Some(outgoing) = outgoing.upgrade() {
+        outgoing.close();
+    }
+}
+
+impl K8Client {
+    /// run command in container of pod. stdin of process is only open if stdin is set,
+    /// stderr is merged with stdout if tty is set
+    pub async fn exec<C, T>(
+        &self,
+        namespace: &str,
+        pod_name: &str,
+        container_name: &str,
+        command: C,
+        stdin: bool,
+        tty: bool,
+    ) -> Result<AttachedProcess>
+    where
+        C: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut query = exec_query(container_name, stdin, tty)?;
+        for arg in command {
+            query.push('&');
+            query.push_str(&serde_qs::to_string(&CommandArg {
+                command: arg.as_ref(),
+            })?);
+        }
+        self.attach_to(namespace, pod_name, "/exec", &query, stdin, tty)
+            .await
+    }
+
+    /// attach to main process of container, container must have `stdin` and `tty` set
+    /// in its spec to interact with process
+    pub async fn attach(
+        &self,
+        namespace: &str,
+        pod_name: &str,
+        container_name: &str,
+        stdin: bool,
+        tty: bool,
+    ) -> Result<AttachedProcess> {
+        let query = exec_query(container_name, stdin, tty)?;
+        self.attach_to(namespace, pod_name, "/attach", &query, stdin, tty)
+            .await
+    }
+
+    async fn attach_to(
+        &self,
+        namespace: &str,
+        pod_name: &str,
+        sub_resource: &str,
+        query: &str,
+        stdin: bool,
+        tty: bool,
+    ) -> Result<AttachedProcess> {
+        let uri = item_uri::<PodSpec>(
+            self.hostname(),
+            pod_name,
+            namespace,
+            Some(sub_resource),
+            Some(query),
+        )?;
+        let (stream, protocol) = self.connect_ws(uri, &[V5_CHANNEL, V4_CHANNEL]).await?;
+        Ok(AttachedProcess::new(stream, &protocol, stdin, !tty))
+    }
+}
+
+fn exec_query(container_name: &str, stdin: bool, tty: bool) -> Result<String> {
+    Ok(serde_qs::to_string(&ExecQuery {
+        container: container_name,
+        stdin,
+        stdout: true,
+        stderr: !tty,
+        tty,
+    })?)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{ExecStatus, StatusMessage};
+
+    fn status(value: serde_json::Value) -> ExecStatus {
+        serde_json::from_value::<StatusMessage>(value)
+            .expect("status")
+            .into()
+    }
+
+    #[test]
+    fn test_exec_status() {
+        let success = status(serde_json::json!({"metadata": {}, "status": "Success"}));
+        assert!(success.success());
+
+        let failure = status(serde_json::json!({
+            "metadata": {},
+            "status": "Failure",
+            "message": "command terminated with non-zero exit code: exit status 2",
+            "reason": "NonZeroExitCode",
+            "details": {"causes": [{"reason": "ExitCode", "message": "2"}]}
+        }));
+        assert_eq!(failure.exit_code, Some(2));
+        assert!(!failure.success());
+
+        let error = status(serde_json::json!({
+            "metadata": {},
+            "status": "Failure",
+            "message": "executable file not found in $PATH",
+            "reason": "InternalError"
+        }));
+        assert_eq!(error.exit_code, None);
+    }
+}
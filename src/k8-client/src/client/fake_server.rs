@@ -0,0 +1,884 @@
+//!
+//! # Fake API Server
+//!
+//! Serves objects of [MemoryClient] over the Kubernetes REST API, so [K8Client] can be tested
+//! end to end without cluster.
+//! Server listens on plain HTTP at local address, types must be registered to be served.
+//!
+//! ```ignore
+//! let server = FakeApiServer::builder()
+//!     .register::<PodSpec>()
+//!     .register::<ServiceSpec>()
+//!     .start()
+//!     .await?;
+//! let client = server.k8_client()?;
+//! ```
+//!
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex as StdMutex, PoisonError};
+
+use anyhow::{anyhow, Result};
+use async_channel::{unbounded, Receiver, Sender};
+use async_trait::async_trait;
+use futures_util::future::{select, Either};
+use futures_util::stream::StreamExt;
+use hyper::body::{to_bytes, Bytes};
+use hyper::header::CONTENT_TYPE;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, trace};
+
+use fluvio_future::net::{TcpListener, TcpStream};
+use fluvio_future::task::spawn;
+use k8_config::{
+    Cluster, ClusterDetail, Context, ContextDetail, K8Config, KubeConfig, KubeContext, User,
+    UserDetail,
+};
+use k8_metadata_client::{
+    ApplyOptions, K8Error, K8ErrorExt, ListArg, MetadataClient, NameSpace, PatchMergeType,
+};
+use k8_types::options::{DeleteOptions, PropogationPolicy};
+use k8_types::{
+    ApiResource, DeleteStatus, InputK8Obj, InputObjectMeta, K8Obj, K8Watch, MetaStatus, Spec,
+    StatusEnum,
+};
+
+use super::client_impl::VersionInfo;
+use super::discovery::{
+    ApiGroup, ApiGroupList, ApiResourceInfo, ApiResourceList, ApiVersions, GroupVersionForDiscovery,
+};
+use super::memory::MemoryClient;
+use super::{HyperTlsStream, K8Client};
+
+#[cfg(feature = "ws")]
+mod exec;
+#[cfg(feature = "ws")]
+mod port_forward;
+#[cfg(feature = "ws")]
+mod ws;
+#[cfg(feature = "ws")]
+pub use exec::{FakeExecHandler, FakeExecOutput};
+
+const NAMESPACE: &str = "default";
+const CONTEXT: &str = "fake";
+const VERBS: [&str; 8] = [
+    "create",
+    "delete",
+    "get",
+    "list",
+    "patch",
+    "update",
+    "watch",
+    "deletecollection",
+];
+
+/// query parameters understood by server
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase", default)]
+struct Query {
+    #[serde(rename = "continue")]
+    continu: Option<String>,
+    field_selector: Option<String>,
+    label_selector: Option<String>,
+    limit: Option<u32>,
+    resource_version: Option<String>,
+    watch: Option<bool>,
+    field_manager: Option<String>,
+    force: Option<bool>,
+    container: Option<String>,
+}
+
+/// request to resource of registered type
+struct ResourceRequest {
+    method: Method,
+    namespace: Option<String>,
+    name: Option<String>,
+    subresource: Option<String>,
+    query: Query,
+    content_type: Option<String>,
+    body: Bytes,
+    /// closed by [FakeApiServer::close_watches]
+    watch_closed: Receiver<()>,
+}
+
+impl ResourceRequest {
+    fn namespace(&self) -> NameSpace {
+        match &self.namespace {
+            Some(namespace) => NameSpace::Named(namespace.clone()),
+            None => NameSpace::All,
+        }
+    }
+
+    fn metadata(&self, name: &str) -> InputObjectMeta {
+        InputObjectMeta::named(name, self.namespace.as_deref().unwrap_or_default())
+    }
+}
+
+/// handles requests for single type
+#[async_trait]
+trait Resource: Send + Sync {
+    /// group, version and discovery info of resource
+    fn discovery(&self) -> (ApiResource, ApiResourceInfo);
+
+    async fn handle(
+        &self,
+        client: Arc<MemoryClient>,
+        request: ResourceRequest,
+    ) -> Result<Response<Body>>;
+}
+
+struct TypedResource<S>(PhantomData<fn() -> S>);
+
+#[async_trait]
+impl<S> Resource for TypedResource<S>
+where
+    S: Spec + 'static,
+{
+    fn discovery(&self) -> (ApiResource, ApiResourceInfo) {
+        let crd = S::metadata();
+        let info = ApiResourceInfo {
+            name: crd.names.plural.to_owned(),
+            singular_name: crd.names.singular.to_owned(),
+            namespaced: S::NAME_SPACED,
+            kind: crd.names.kind.to_owned(),
+            verbs: VERBS.iter().map(|verb| verb.to_string()).collect(),
+            ..Default::default()
+        };
+        (ApiResource::from_spec::<S>(), info)
+    }
+
+    async fn handle(
+        &self,
+        client: Arc<MemoryClient>,
+        request: ResourceRequest,
+    ) -> Result<Response<Body>> {
+        let subresource = request.subresource.as_deref();
+        let name = request.name.as_deref();
+        match (&request.method, name, subresource) {
+            (&Method::GET, None, None) if request.query.watch == Some(true) => {
+                Ok(watch::<S>(client, &request))
+            }
+            (&Method::GET, None, None) => {
+                let option = ListArg {
+                    field_selector: request.query.field_selector.clone(),
+                    label_selector: request.query.label_selector.clone(),
+                    limit: request.query.limit,
+                    continu: request.query.continu.clone(),
+                    ..Default::default()
+                };
+                let list = client.list::<S>(request.namespace(), Some(option)).await?;
+                json_response(StatusCode::OK, &list)
+            }
+            (&Method::GET, Some(name), None | Some("status")) => {
+                match client
+                    .retrieve_item::<S, _>(&request.metadata(name))
+                    .await?
+                {
+                    Some(k8_obj) => json_response(StatusCode::OK, &k8_obj),
+                    None => Err(object_not_found::<S>(name)),
+                }
+            }
+            (&Method::POST, None, None) => {
+                let mut input = as_input(decode::<S>(&request.body)?);
+                if let Some(namespace) = &request.namespace {
+                    if input.metadata.namespace.is_empty() {
+                        input.metadata.namespace = namespace.clone();
+                    }
+                }
+                let k8_obj = client.create_item(input).await?;
+                json_response(StatusCode::CREATED, &k8_obj)
+            }
+            (&Method::PUT, Some(_), None) => {
+                let update = decode::<S>(&request.body)?.as_update();
+                json_response(StatusCode::OK, &client.replace_item(update).await?)
+            }
+            (&Method::PUT, Some(_), Some("status")) => {
+                let k8_obj = decode::<S>(&request.body)?;
+                let update = k8_obj.as_status_update(k8_obj.status.clone());
+                json_response(StatusCode::OK, &client.update_status(&update).await?)
+            }
+            (&Method::PATCH, Some(name), subresource) => {
+                let metadata = request.metadata(name);
+                let content_type = request.content_type.as_deref().unwrap_or_default();
+                if content_type == PatchMergeType::Apply(ApplyOptions::default()).content_type() {
+                    let mut input = as_input(serde_yaml::from_slice::<K8Obj<S>>(&request.body)?);
+                    input.metadata.namespace = metadata.namespace;
+                    let mut options =
+                        ApplyOptions::new(request.query.field_manager.clone().unwrap_or_default());
+                    if request.query.force == Some(true) {
+                        options = options.force();
+                    }
+                    let k8_obj = client.server_side_apply(input, options).await?;
+                    return json_response(StatusCode::OK, &k8_obj);
+                }
+
+                let merge_type = merge_type(content_type)?;
+                let patch: serde_json::Value = serde_json::from_slice(&request.body)?;
+                let k8_obj = match subresource {
+                    None => client.patch::<S, _>(&metadata, &patch, merge_type).await?,
+                    Some(subresource) => {
+                        client
+                            .patch_subresource::<S, _>(
+                                &metadata,
+                                format!("/{subresource}"),
+                                &patch,
+                                merge_type,
+                            )
+                            .await?
+                    }
+                };
+                json_response(StatusCode::OK, &k8_obj)
+            }
+            (&Method::DELETE, Some(name), None) => {
+                let option = delete_option(&request.body)?;
+                let metadata = request.metadata(name);
+                if client.retrieve_item::<S, _>(&metadata).await?.is_none() {
+                    return Err(object_not_found::<S>(name));
+                }
+                match client
+                    .delete_item_with_option::<S, _>(&metadata, option)
+                    .await?
+                {
+                    DeleteStatus::Deleted(mut status) => {
+                        status.api_version = "v1".to_owned();
+                        status.kind = "Status".to_owned();
+                        status.code = Some(StatusCode::OK.as_u16());
+                        json_response(StatusCode::OK, &status)
+                    }
+                    DeleteStatus::ForegroundDelete(k8_obj) => {
+                        json_response(StatusCode::OK, &k8_obj)
+                    }
+                }
+            }
+            _ => Ok(status_response(failure(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "MethodNotAllowed",
+                format!("{} is not supported", request.method),
+            ))),
+        }
+    }
+}
+
+/// stream of newline delimited watch events.
+/// events are forwarded by task which ends at first event after client disconnects
+fn watch<S>(client: Arc<MemoryClient>, request: &ResourceRequest) -> Response<Body>
+where
+    S: Spec + 'static,
+{
+    let namespace = request.namespace();
+    let version = request.query.resource_version.clone();
+    let watch_closed = request.watch_closed.clone();
+    let (sender, receiver) = unbounded::<Result<Bytes, Infallible>>();
+
+    spawn(async move {
+        let mut events = client.watch_stream_since::<S, _>(namespace, version);
+        loop {
+            // closing is checked first, so no event is sent after watches are closed
+            let result = match select(Box::pin(watch_closed.recv()), events.next()).await {
+                Either::Right((Some(result), _)) => result,
+                Either::Right((None, _)) => return,
+                Either::Left(_) => {
+                    debug!("{}: watch closed", S::label());
+                    return;
+                }
+            };
+            let events: Vec<K8Watch<S>> = match result
+                .and_then(|events| events.into_iter().collect::<Result<Vec<K8Watch<S>>>>())
+            {
+                Ok(events) => events,
+                Err(err) => vec![K8Watch::ERROR(error_status(err))],
+            };
+            for event in events {
+                let last = matches!(event, K8Watch::ERROR(_));
+                let mut line = match serde_json::to_vec(&event) {
+                    Ok(line) => line,
+                    Err(err) => {
+                        error!("{}: error encoding watch event: {}", S::label(), err);
+                        return;
+                    }
+                };
+                line.push(b'\n');
+                if sender.send(Ok(line.into())).await.is_err() || last {
+                    debug!("{}: watch ended", S::label());
+                    return;
+                }
+            }
+        }
+    });
+
+    Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::wrap_stream(receiver))
+        .unwrap_or_default()
+}
+
+fn merge_type(content_type: &str) -> Result<PatchMergeType> {
+    [
+        PatchMergeType::Json,
+        PatchMergeType::JsonMerge,
+        PatchMergeType::StrategicMerge,
+    ]
+    .into_iter()
+    .find(|merge_type| merge_type.content_type() == content_type)
+    .ok_or_else(|| anyhow!("unsupported patch content type: {}", content_type))
+}
+
+fn delete_option(body: &[u8]) -> Result<Option<DeleteOptions>> {
+    if body.is_empty() {
+        return Ok(None);
+    }
+    let value: serde_json::Value = serde_json::from_slice(body)?;
+    let propagation_policy = match value.get("propagationPolicy").and_then(|v| v.as_str()) {
+        Some("Orphan") => Some(PropogationPolicy::Orphan),
+        Some("Background") => Some(PropogationPolicy::Background),
+        Some("Foreground") => Some(PropogationPolicy::Foreground),
+        Some(policy) => return Err(anyhow!("invalid propagation policy: {}", policy)),
+        None => None,
+    };
+    Ok(Some(DeleteOptions {
+        propagation_policy,
+        ..Default::default()
+    }))
+}
+
+fn json_response<T: Serialize>(status: StatusCode, value: &T) -> Result<Response<Body>> {
+    Ok(Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(value)?.into())?)
+}
+
+fn status_response(status: MetaStatus) -> Response<Body> {
+    let code = status
+        .code
+        .and_then(|code| StatusCode::from_u16(code).ok())
+        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    json_response(code, &status).unwrap_or_else(|err| {
+        error!("error encoding status: {}", err);
+        let mut response = Response::default();
+        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        response
+    })
+}
+
+fn failure(code: StatusCode, reason: &str, message: String) -> MetaStatus {
+    MetaStatus {
+        api_version: "v1".to_owned(),
+        code: Some(code.as_u16()),
+        details: None,
+        kind: "Status".to_owned(),
+        message: Some(message),
+        reason: Some(reason.to_owned()),
+        status: StatusEnum::FAILURE,
+    }
+}
+
+fn object_not_found<S: Spec>(name: &str) -> anyhow::Error {
+    K8Error::from(failure(
+        StatusCode::NOT_FOUND,
+        "NotFound",
+        format!("{} \"{}\" not found", S::metadata().names.plural, name),
+    ))
+    .into_anyhow()
+}
+
+/// object sent by client, fields which are not set are defaulted as in API server
+fn decode<S: Spec>(body: &[u8]) -> Result<K8Obj<S>> {
+    Ok(serde_json::from_slice(body)?)
+}
+
+/// object to be created, metadata set by server is ignored
+fn as_input<S: Spec>(k8_obj: K8Obj<S>) -> InputK8Obj<S> {
+    let metadata = k8_obj.metadata;
+    InputK8Obj {
+        api_version: k8_obj.api_version,
+        kind: k8_obj.kind,
+        metadata: InputObjectMeta {
+            name: metadata.name,
+            labels: metadata.labels,
+            namespace: metadata.namespace,
+            owner_references: metadata.owner_references,
+            finalizers: metadata.finalizers,
+            annotations: metadata.annotations,
+        },
+        spec: k8_obj.spec,
+        header: k8_obj.header,
+    }
+}
+
+/// status returned for error from memory client
+fn error_status(err: anyhow::Error) -> MetaStatus {
+    if let Some(status) = err.k8_error().and_then(K8Error::status) {
+        status.clone()
+    } else if err.is::<serde_json::Error>() || err.is::<serde_yaml::Error>() {
+        failure(StatusCode::BAD_REQUEST, "BadRequest", err.to_string())
+    } else {
+        failure(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "InternalError",
+            err.to_string(),
+        )
+    }
+}
+
+fn not_found(path: &str) -> Response<Body> {
+    status_response(failure(
+        StatusCode::NOT_FOUND,
+        "NotFound",
+        format!("the server could not find the requested resource: {path}"),
+    ))
+}
+
+/// path of resources of type without host, ex: `api/v1/pods`
+fn resource_path<S: Spec>() -> String {
+    let crd = S::metadata();
+    match crd.group {
+        "core" => format!("api/{}/{}", crd.version, crd.names.plural),
+        group => format!("apis/{}/{}/{}", group, crd.version, crd.names.plural),
+    }
+}
+
+struct State {
+    client: Arc<MemoryClient>,
+    resources: HashMap<String, Arc<dyn Resource>>,
+    /// logs keyed by `<namespace>/<pod>/<container>`
+    logs: StdMutex<HashMap<String, String>>,
+    /// open watches end when sender is closed
+    watches: StdMutex<(Sender<()>, Receiver<()>)>,
+    /// exec handlers keyed by `<namespace>/<pod>`
+    #[cfg(feature = "ws")]
+    execs: StdMutex<HashMap<String, FakeExecHandler>>,
+    /// forwarded ports keyed by `<namespace>/<pod>`
+    #[cfg(feature = "ws")]
+    port_forwards: StdMutex<HashMap<String, HashMap<u16, SocketAddr>>>,
+}
+
+impl State {
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        let path = request.uri().path().to_owned();
+        debug!(method = %request.method(), %path, "request");
+        let response = match self.route(request).await {
+            Ok(response) => response,
+            Err(err) => {
+                debug!(%path, %err, "request failed");
+                status_response(error_status(err))
+            }
+        };
+        trace!(%path, status = %response.status(), "response");
+        response
+    }
+
+    async fn route(&self, request: Request<Body>) -> Result<Response<Body>> {
+        let path = request.uri().path().trim_matches('/').to_owned();
+        let segments: Vec<&str> = path.split('/').collect();
+
+        let (api, rest) = match segments.as_slice() {
+            ["version"] => return json_response(StatusCode::OK, &version_info()),
+            ["api"] => return json_response(StatusCode::OK, &self.api_versions()),
+            ["apis"] => return json_response(StatusCode::OK, &self.api_groups()),
+            ["api", version, rest @ ..] => (format!("api/{version}"), rest),
+            ["apis", group, version, rest @ ..] => (format!("apis/{group}/{version}"), rest),
+            _ => return Ok(not_found(&path)),
+        };
+        let (namespace, plural, rest) = match rest {
+            ["namespaces", namespace, plural, rest @ ..] => {
+                (Some(namespace.to_string()), *plural, rest)
+            }
+            [plural, rest @ ..] => (None, *plural, rest),
+            [] => {
+                return match self.api_resources(&api) {
+                    Some(list) => json_response(StatusCode::OK, &list),
+                    None => Ok(not_found(&path)),
+                }
+            }
+        };
+        let name = rest.first().map(|name| name.to_string());
+        let subresource = (rest.len() > 1).then(|| rest[1..].join("/"));
+
+        #[cfg(feature = "ws")]
+        if plural == "pods" && matches!(subresource.as_deref(), Some("exec" | "attach")) {
+            let key = format!(
+                "{}/{}",
+                namespace.unwrap_or_default(),
+                name.unwrap_or_default()
+            );
+            let handler = self
+                .execs
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&key)
+                .cloned();
+            return match handler {
+                Some(handler) => exec::upgrade(request, handler),
+                None => Ok(not_found(&path)),
+            };
+        }
+
+        #[cfg(feature = "ws")]
+        if plural == "pods" && subresource.as_deref() == Some("portforward") {
+            let key = format!(
+                "{}/{}",
+                namespace.unwrap_or_default(),
+                name.unwrap_or_default()
+            );
+            let targets = self
+                .port_forwards
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&key)
+                .cloned();
+            return match targets {
+                Some(targets) => port_forward::upgrade(request, targets),
+                None => Ok(not_found(&path)),
+            };
+        }
+
+        let query: Query = serde_qs::from_str(request.uri().query().unwrap_or_default())?;
+
+        if plural == "pods" && subresource.as_deref() == Some("log") {
+            let key = format!(
+                "{}/{}/{}",
+                namespace.unwrap_or_default(),
+                name.unwrap_or_default(),
+                query.container.unwrap_or_default()
+            );
+            let log = self
+                .logs
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&key)
+                .cloned();
+            return match log {
+                Some(log) => Ok(Response::builder()
+                    .header(CONTENT_TYPE, "text/plain")
+                    .body(log.into())?),
+                None => Ok(not_found(&path)),
+            };
+        }
+
+        let Some(resource) = self.resources.get(&format!("{api}/{plural}")).cloned() else {
+            return Ok(not_found(&path));
+        };
+
+        let method = request.method().clone();
+        let content_type = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_owned());
+        let body = to_bytes(request.into_body()).await?;
+        let watch_closed = self
+            .watches
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .1
+            .clone();
+
+        resource
+            .handle(
+                self.client.clone(),
+                ResourceRequest {
+                    method,
+                    namespace,
+                    name,
+                    subresource,
+                    query,
+                    content_type,
+                    body,
+                    watch_closed,
+                },
+            )
+            .await
+    }
+}
+
+impl State {
+    fn discovery(&self) -> impl Iterator<Item = (ApiResource, ApiResourceInfo)> + '_ {
+        self.resources.values().map(|resource| resource.discovery())
+    }
+
+    /// response of `/api`
+    fn api_versions(&self) -> ApiVersions {
+        let versions: BTreeSet<String> = self
+            .discovery()
+            .filter(|(resource, _)| resource.is_core())
+            .map(|(resource, _)| resource.version)
+            .collect();
+        ApiVersions {
+            kind: "APIVersions".to_owned(),
+            versions: versions.into_iter().collect(),
+        }
+    }
+
+    /// response of `/apis`, first version of group is preferred
+    fn api_groups(&self) -> ApiGroupList {
+        let mut groups: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (resource, _) in self.discovery().filter(|(resource, _)| !resource.is_core()) {
+            groups
+                .entry(resource.group)
+                .or_default()
+                .insert(resource.version);
+        }
+        ApiGroupList {
+            kind: "APIGroupList".to_owned(),
+            api_version: "v1".to_owned(),
+            groups: groups
+                .into_iter()
+                .map(|(name, versions)| {
+                    let versions: Vec<GroupVersionForDiscovery> = versions
+                        .into_iter()
+                        .map(|version| GroupVersionForDiscovery {
+                            group_version: format!("{name}/{version}"),
+                            version,
+                        })
+                        .collect();
+                    ApiGroup {
+                        preferred_version: versions.first().cloned(),
+                        name,
+                        versions,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// response of `/api/{version}` or `/apis/{group}/{version}`
+    fn api_resources(&self, api: &str) -> Option<ApiResourceList> {
+        let mut group_version = None;
+        let mut resources = vec![];
+        for (resource, info) in self.discovery() {
+            let prefix = if resource.is_core() {
+                format!("api/{}", resource.version)
+            } else {
+                format!("apis/{}/{}", resource.group, resource.version)
+            };
+            if prefix == api {
+                group_version = Some(resource.api_version());
+                resources.push(info);
+            }
+        }
+        resources.sort_by(|a, b| a.name.cmp(&b.name));
+        Some(ApiResourceList {
+            kind: "APIResourceList".to_owned(),
+            api_version: "v1".to_owned(),
+            group_version: group_version?,
+            resources,
+        })
+    }
+}
+
+fn version_info() -> VersionInfo {
+    VersionInfo {
+        major: "1".to_owned(),
+        minor: "30".to_owned(),
+        git_version: "v1.30.0-fake".to_owned(),
+        platform: "linux/amd64".to_owned(),
+        ..Default::default()
+    }
+}
+
+async fn serve(state: Arc<State>, stream: TcpStream) {
+    let service = service_fn(move |request| {
+        let state = state.clone();
+        async move { Ok::<_, Infallible>(state.handle(request).await) }
+    });
+    if let Err(err) = Http::new()
+        .http1_only(true)
+        .serve_connection(HyperTlsStream::Plain(stream), service)
+        .with_upgrades()
+        .await
+    {
+        debug!(%err, "connection closed");
+    }
+}
+
+/// builder of [FakeApiServer]
+#[derive(Default)]
+pub struct FakeApiServerBuilder {
+    client: Option<Arc<MemoryClient>>,
+    resources: HashMap<String, Arc<dyn Resource>>,
+}
+
+impl FakeApiServerBuilder {
+    /// serve objects of existing client instead of new one
+    pub fn with_client(mut self, client: Arc<MemoryClient>) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// serve objects of type
+    pub fn register<S>(mut self) -> Self
+    where
+        S: Spec + 'static,
+    {
+        self.resources.insert(
+            resource_path::<S>(),
+            Arc::new(TypedResource::<S>(PhantomData)),
+        );
+        self
+    }
+
+    /// listen on local address picked by OS
+    pub async fn start(self) -> Result<FakeApiServer> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(State {
+            client: self.client.unwrap_or_default(),
+            resources: self.resources,
+            logs: StdMutex::new(HashMap::new()),
+            watches: StdMutex::new(unbounded()),
+            #[cfg(feature = "ws")]
+            execs: StdMutex::new(HashMap::new()),
+            #[cfg(feature = "ws")]
+            port_forwards: StdMutex::new(HashMap::new()),
+        });
+        let (shutdown, closed) = unbounded();
+
+        debug!(%address, "fake api server started");
+        spawn(accept(listener, state.clone(), closed));
+
+        Ok(FakeApiServer {
+            address,
+            state,
+            _shutdown: shutdown,
+        })
+    }
+}
+
+/// accept connections until server is dropped
+async fn accept(listener: TcpListener, state: Arc<State>, closed: Receiver<()>) {
+    loop {
+        match select(Box::pin(listener.accept()), Box::pin(closed.recv())).await {
+            Either::Left((Ok((stream, _)), _)) => {
+                spawn(serve(state.clone(), stream));
+            }
+            Either::Left((Err(err), _)) => {
+                error!(%err, "fake api server accept failed");
+                return;
+            }
+            Either::Right(_) => {
+                debug!("fake api server stopped");
+                return;
+            }
+        }
+    }
+}
+
+/// Kubernetes API server backed by [MemoryClient].
+/// Server stops accepting connections when dropped
+pub struct FakeApiServer {
+    address: SocketAddr,
+    state: Arc<State>,
+    _shutdown: Sender<()>,
+}
+
+impl FakeApiServer {
+    pub fn builder() -> FakeApiServerBuilder {
+        FakeApiServerBuilder::default()
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// url of server, ex: `http://127.0.0.1:1234`
+    pub fn host(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// client whose objects are served
+    pub fn memory_client(&self) -> Arc<MemoryClient> {
+        self.state.client.clone()
+    }
+
+    /// end open watches, as API server does when watch times out.
+    /// watches started afterwards are not affected
+    pub fn close_watches(&self) {
+        let mut watches = self
+            .state
+            .watches
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (sender, _) = std::mem::replace(&mut *watches, unbounded());
+        sender.close();
+    }
+
+    /// log returned for container of pod
+    pub fn set_log(&self, namespace: &str, pod: &str, container: &str, log: impl Into<String>) {
+        self.state
+            .logs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(format!("{namespace}/{pod}/{container}"), log.into());
+    }
+
+    /// handler of commands run in pod with `exec` or `attach`
+    #[cfg(feature = "ws")]
+    pub fn set_exec<F>(&self, namespace: &str, pod: &str, handler: F)
+    where
+        F: Fn(&[String], &[u8]) -> FakeExecOutput + Send + Sync + 'static,
+    {
+        self.state
+            .execs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(format!("{namespace}/{pod}"), Arc::new(handler));
+    }
+
+    /// forward port of pod to target address. ports of pod which are not set are refused
+    #[cfg(feature = "ws")]
+    pub fn set_port_forward(&self, namespace: &str, pod: &str, port: u16, target: SocketAddr) {
+        self.state
+            .port_forwards
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(format!("{namespace}/{pod}"))
+            .or_default()
+            .insert(port, target);
+    }
+
+    /// config pointing to this server without credentials.
+    /// server is plain `http` which [K8Client::new] rejects, use [FakeApiServer::k8_client]
+    pub fn k8_config(&self) -> K8Config {
+        let config = KubeConfig {
+            api_version: "v1".to_owned(),
+            kind: "Config".to_owned(),
+            clusters: vec![Cluster {
+                name: CONTEXT.to_owned(),
+                cluster: ClusterDetail {
+                    server: self.host(),
+                    ..Default::default()
+                },
+            }],
+            contexts: vec![Context {
+                name: CONTEXT.to_owned(),
+                context: ContextDetail {
+                    cluster: CONTEXT.to_owned(),
+                    user: CONTEXT.to_owned(),
+                    namespace: Some(NAMESPACE.to_owned()),
+                },
+            }],
+            current_context: CONTEXT.to_owned(),
+            users: vec![User {
+                name: CONTEXT.to_owned(),
+                user: UserDetail::default(),
+            }],
+            ..Default::default()
+        };
+        K8Config::KubeConfig(KubeContext {
+            namespace: NAMESPACE.to_owned(),
+            api_path: self.host(),
+            config,
+        })
+    }
+
+    /// client connected to this server
+    pub fn k8_client(&self) -> Result<K8Client> {
+        K8Client::new_with_http(self.k8_config())
+    }
+}
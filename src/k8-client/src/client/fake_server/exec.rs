@@ -0,0 +1,128 @@
+//!
+//! # Exec
+//!
+//! Commands run in pods of fake server, with `exec` or `attach`, are answered by handlers
+//! set with [FakeApiServer::set_exec](super::FakeApiServer::set_exec).
+//!
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use hyper::{Body, Request, Response};
+use serde::Deserialize;
+use serde_json::json;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::client::ws::{WsStream, CLOSE, ERROR, STDERR, STDIN, STDOUT, V4_CHANNEL, V5_CHANNEL};
+
+/// output of command run in fake server
+#[derive(Debug, Default, Clone)]
+pub struct FakeExecOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub exit_code: i32,
+}
+
+/// handler of command, called with command and stdin of process.
+/// command is empty for `attach`
+pub type FakeExecHandler = Arc<dyn Fn(&[String], &[u8]) -> FakeExecOutput + Send + Sync>;
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ExecQuery {
+    #[serde(skip)]
+    command: Vec<String>,
+    stdin: bool,
+    stdout: bool,
+    stderr: bool,
+    tty: bool,
+}
+
+#[derive(Deserialize)]
+struct CommandArg {
+    command: String,
+}
+
+/// command is repeated for each argument
+fn parse_query(query: &str) -> Result<ExecQuery> {
+    let (commands, rest): (Vec<&str>, Vec<&str>) = query
+        .split('&')
+        .partition(|pair| pair.starts_with("command="));
+    let mut exec: ExecQuery = serde_qs::from_str(&rest.join("&"))?;
+    exec.command = commands
+        .into_iter()
+        .map(|pair| Ok(serde_qs::from_str::<CommandArg>(pair)?.command))
+        .collect::<Result<_>>()?;
+    Ok(exec)
+}
+
+/// accept WebSocket upgrade, process is run by handler after upgrade
+pub(super) fn upgrade(request: Request<Body>, handler: FakeExecHandler) -> Result<Response<Body>> {
+    let query = parse_query(request.uri().query().unwrap_or_default())?;
+    super::ws::upgrade(
+        request,
+        &[V5_CHANNEL, V4_CHANNEL],
+        move |stream, protocol| run(stream, query, protocol == V5_CHANNEL, handler),
+    )
+}
+
+/// read stdin until it is closed, then send output and status of handler
+async fn run(
+    stream: WsStream,
+    query: ExecQuery,
+    close_supported: bool,
+    handler: FakeExecHandler,
+) -> Result<()> {
+    let (mut sink, mut stream) = stream.split();
+
+    // without close message, end of stdin is unknown so it is not read
+    let mut stdin = vec![];
+    if query.stdin && close_supported {
+        while let Some(message) = stream.next().await {
+            match message? {
+                Message::Binary(data) => match data.split_first() {
+                    Some((&STDIN, payload)) => stdin.extend_from_slice(payload),
+                    Some((&CLOSE, [STDIN])) => break,
+                    _ => {}
+                },
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+    }
+
+    let output = handler(&query.command, &stdin);
+    // terminal merges stderr with stdout
+    let (stderr, stderr_enabled) = if query.tty {
+        (STDOUT, query.stdout)
+    } else {
+        (STDERR, query.stderr)
+    };
+    for (channel, enabled, data) in [
+        (STDOUT, query.stdout, output.stdout),
+        (stderr, stderr_enabled, output.stderr),
+    ] {
+        if enabled && !data.is_empty() {
+            sink.send(Message::Binary([&[channel], data.as_slice()].concat()))
+                .await?;
+        }
+    }
+
+    let status = if output.exit_code == 0 {
+        json!({"metadata": {}, "status": "Success"})
+    } else {
+        json!({
+            "metadata": {},
+            "status": "Failure",
+            "message": format!("command terminated with non-zero exit code: exit status {}", output.exit_code),
+            "reason": "NonZeroExitCode",
+            "details": {"causes": [{"reason": "ExitCode", "message": output.exit_code.to_string()}]}
+        })
+    };
+    sink.send(Message::Binary(
+        [&[ERROR], serde_json::to_vec(&status)?.as_slice()].concat(),
+    ))
+    .await?;
+    sink.close().await?;
+    Ok(())
+}
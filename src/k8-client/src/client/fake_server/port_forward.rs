@@ -0,0 +1,130 @@
+//!
+//! # Port Forward
+//!
+//! Ports of pods in fake server are forwarded to local addresses set with
+//! [FakeApiServer::set_port_forward](super::FakeApiServer::set_port_forward).
+//!
+use std::collections::HashMap;
+use std::net::{Shutdown, SocketAddr};
+
+use anyhow::{anyhow, Result};
+use async_channel::{unbounded, Sender};
+use futures_util::io::{AsyncReadExt, AsyncWriteExt};
+use futures_util::StreamExt;
+use hyper::{Body, Request, Response};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::debug;
+
+use fluvio_future::net::TcpStream;
+use fluvio_future::task::spawn;
+
+use crate::client::ws::{next_binary, write_messages, WsStream, V4_CHANNEL};
+
+/// ports are repeated, or separated by comma
+fn parse_ports(query: &str) -> Result<Vec<u16>> {
+    let ports = query
+        .split('&')
+        .filter_map(|pair| pair.strip_prefix("ports="))
+        .flat_map(|ports| ports.split(','))
+        .map(|port| port.parse().map_err(|_| anyhow!("invalid port: {}", port)))
+        .collect::<Result<Vec<u16>>>()?;
+    if ports.is_empty() {
+        return Err(anyhow!("at least one port must be specified"));
+    }
+    Ok(ports)
+}
+
+/// accept WebSocket upgrade, ports are connected to targets after upgrade
+pub(super) fn upgrade(
+    request: Request<Body>,
+    targets: HashMap<u16, SocketAddr>,
+) -> Result<Response<Body>> {
+    let ports = parse_ports(request.uri().query().unwrap_or_default())?;
+    let ports = ports
+        .into_iter()
+        .map(|port| (port, targets.get(&port).copied()))
+        .collect();
+    super::ws::upgrade(request, &[V4_CHANNEL], move |stream, _| run(stream, ports))
+}
+
+/// connect each port to its target and copy data until client closes connection.
+/// connection is closed by server when all targets have closed their connections
+async fn run(stream: WsStream, ports: Vec<(u16, Option<SocketAddr>)>) -> Result<()> {
+    let (sink, mut stream) = stream.split();
+    let (outgoing, outgoing_receiver) = unbounded();
+    spawn(write_messages(sink, outgoing_receiver));
+
+    let mut connections = Vec::with_capacity(ports.len());
+    for (index, (port, target)) in ports.into_iter().enumerate() {
+        let data = (index * 2) as u8;
+        let error = data + 1;
+        for channel in [data, error] {
+            let [low, high] = port.to_le_bytes();
+            outgoing
+                .send(Message::Binary(vec![channel, low, high]))
+                .await?;
+        }
+
+        let connection = match target {
+            Some(target) => TcpStream::connect(target)
+                .await
+                .map_err(|err| anyhow!("error forwarding port {}: {}", port, err)),
+            None => Err(anyhow!(
+                "error forwarding port {}: connection refused",
+                port
+            )),
+        };
+        match connection {
+            Ok(connection) => {
+                spawn(copy_output(connection.clone(), data, outgoing.clone()));
+                connections.push(Some(connection));
+            }
+            Err(err) => {
+                debug!(%err, "port forward failed");
+                let message = [&[error], err.to_string().as_bytes()].concat();
+                outgoing.send(Message::Binary(message)).await?;
+                connections.push(None);
+            }
+        }
+    }
+
+    // outgoing is kept open by connections to targets
+    drop(outgoing);
+
+    while let Some(message) = next_binary(&mut stream).await {
+        let Some((&channel, payload)) = message.split_first() else {
+            continue;
+        };
+        if channel % 2 != 0 {
+            continue;
+        }
+        if let Some(Some(connection)) = connections.get_mut(channel as usize / 2) {
+            connection.write_all(payload).await?;
+        }
+    }
+
+    for connection in connections.into_iter().flatten() {
+        let _ = connection.shutdown(Shutdown::Both);
+    }
+    Ok(())
+}
+
+/// send data read from target on data channel of port
+async fn copy_output(mut connection: TcpStream, channel: u8, outgoing: Sender<Message>) {
+    let mut buf = vec![0; 4096];
+    loop {
+        match connection.read(&mut buf).await {
+            Ok(0) => return,
+            Ok(len) => {
+                let message = [&[channel], &buf[..len]].concat();
+                if outgoing.send(Message::Binary(message)).await.is_err() {
+                    return;
+                }
+            }
+            Err(err) => {
+                debug!(%err, "port forward read failed");
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//!
+//! # WebSocket
+//!
+//! Server side of WebSocket upgrade for streaming subresources of fake server.
+//!
+use std::future::Future;
+
+use anyhow::{anyhow, Result};
+use hyper::header::{
+    HeaderName, CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_PROTOCOL,
+    UPGRADE,
+};
+use hyper::{Body, Request, Response, StatusCode};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::WebSocketStream;
+use tracing::debug;
+
+use fluvio_future::task::spawn;
+
+use crate::client::ws::WsStream;
+
+/// accept WebSocket upgrade with first of `protocols` requested by client.
+/// connection and picked protocol are passed to `run` after upgrade
+pub(super) fn upgrade<F, R>(
+    mut request: Request<Body>,
+    protocols: &[&str],
+    run: F,
+) -> Result<Response<Body>>
+where
+    F: FnOnce(WsStream, String) -> R + Send + 'static,
+    R: Future<Output = Result<()>> + Send,
+{
+    let header = |name: HeaderName| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_owned())
+    };
+    let key = header(SEC_WEBSOCKET_KEY).ok_or_else(|| anyhow!("websocket upgrade is required"))?;
+    let protocol = header(SEC_WEBSOCKET_PROTOCOL)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .find(|protocol| protocols.contains(protocol))
+        .ok_or_else(|| anyhow!("unsupported websocket protocol"))?
+        .to_owned();
+
+    let on_upgrade = hyper::upgrade::on(&mut request);
+    let picked = protocol.clone();
+    spawn(async move {
+        match on_upgrade.await {
+            Ok(upgraded) => {
+                let stream = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                if let Err(err) = run(stream, picked).await {
+                    debug!(%err, "websocket connection failed");
+                }
+            }
+            Err(err) => debug!(%err, "upgrade failed"),
+        }
+    });
+
+    Ok(Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(CONNECTION, "Upgrade")
+        .header(UPGRADE, "websocket")
+        .header(SEC_WEBSOCKET_ACCEPT, derive_accept_key(key.as_bytes()))
+        .header(SEC_WEBSOCKET_PROTOCOL, protocol)
+        .body(Body::empty())?)
+}
@@ -73,23 +73,17 @@ where
 
     /// given continuation, generate list option
     fn list_option(&self, continu: Option<String>) -> ListOptions {
-        let field_selector = match &self.arg {
-            None => None,
-            Some(arg) => arg.field_selector.clone(),
-        };
-
-        let label_selector = match &self.arg {
-            None => None,
-            Some(arg) => arg.label_selector.clone(),
-        };
-
-        ListOptions {
+        let mut option = ListOptions {
             limit: Some(self.limit),
-            continu,
-            field_selector,
-            label_selector,
-            ..Default::default()
+            ..self.arg.clone().map(ListOptions::from).unwrap_or_default()
+        };
+        // resource version is carried in continue token of later chunks
+        if continu.is_some() {
+            option.resource_version = None;
+            option.resource_version_match = None;
+            option.continu = continu;
         }
+        option
     }
 }
 
@@ -1,16 +1,17 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex, PoisonError};
+use std::time::SystemTime;
 use core::fmt::Display;
 
-use anyhow::Result;
-use async_channel::{Sender, Receiver, bounded};
-use async_lock::Mutex;
+use anyhow::{anyhow, Result};
+use async_channel::{Sender, Receiver, unbounded};
 use async_lock::RwLock;
-use tracing::debug;
-use futures_util::FutureExt;
+use tracing::{debug, error};
 use futures_util::StreamExt;
-use futures_util::stream::BoxStream;
-use serde::Serialize;
+use futures_util::stream::{self, BoxStream};
+use rand::Rng;
 use serde::de::DeserializeOwned;
 use serde_yaml::Value;
 
@@ -18,44 +19,155 @@ use k8_types::Spec;
 use k8_types::ListMetadata;
 use k8_types::MetaStatus;
 use k8_types::K8Watch;
-use k8_types::options::DeleteOptions;
+use k8_types::options::{DeleteOptions, PropogationPolicy};
 use k8_metadata_client::TokenStreamResult;
 use k8_metadata_client::PatchMergeType;
+use k8_metadata_client::ApplyOptions;
 use k8_metadata_client::NameSpace;
 use k8_metadata_client::ListArg;
 use k8_metadata_client::MetadataClient;
 use k8_types::ObjectMeta;
-use k8_metadata_client::ObjectKeyNotFound;
+use k8_metadata_client::K8Error;
 use k8_types::UpdateK8ObjStatus;
 use k8_types::DeleteStatus;
 use k8_types::K8List;
 use k8_types::InputK8Obj;
+use k8_types::UpdatedK8Obj;
+use k8_types::StatusEnum;
 use k8_types::K8Meta;
 use k8_types::K8Obj;
 
+use self::files::Files;
+use self::gc::FOREGROUND_DELETION;
+use self::patch::apply_patch;
+use self::selector::Selector;
+
+mod files;
+mod gc;
+mod patch;
+mod selector;
+
+/// number of changes kept per type for watches resuming from older version
+const HISTORY_LIMIT: usize = 1000;
+
+/// version of empty client. version 0 is never returned since watch from 0 means watch from now
+const INITIAL_VERSION: u64 = 1;
+
+/// key of object within store
+fn object_key(namespace: &str, name: &str) -> String {
+    format!("{namespace}/{name}")
+}
+
+/// watcher interested in changes, optionally only in single namespace
+#[derive(Debug)]
+struct Watcher {
+    namespace: Option<String>,
+    sender: Sender<Value>,
+}
+
+/// change sent to watchers
+#[derive(Debug)]
+struct Change {
+    version: u64,
+    namespace: String,
+    event: Value,
+}
+
+#[derive(Debug, Default)]
+struct Watchers {
+    watchers: Vec<Watcher>,
+    /// recent changes, oldest first
+    history: VecDeque<Change>,
+    /// version of newest change dropped from history
+    compacted: u64,
+}
+
 /// Store for specific type
 /// It is used to store data in memory
 /// It also provides the structs used to watch for changes
 #[derive(Debug)]
 struct SpecStore {
-    data: Arc<RwLock<HashMap<String, Value>>>,
-    sender: Arc<Sender<Value>>,
-    receiver: Receiver<Value>,
+    namespaced: bool,
+    /// resource version shared by all stores
+    version: Arc<AtomicU64>,
+    data: RwLock<BTreeMap<String, Value>>,
+    watchers: StdMutex<Watchers>,
+    files: Option<Files>,
 }
 
-impl Default for SpecStore {
-    fn default() -> Self {
-        let (sender, receiver) = bounded(100);
-
+impl SpecStore {
+    /// store with objects loaded from files, history before loaded version is not known
+    fn new(
+        namespaced: bool,
+        version: Arc<AtomicU64>,
+        files: Option<Files>,
+        data: BTreeMap<String, Value>,
+        loaded_version: u64,
+    ) -> Self {
         Self {
-            data: Arc::new(RwLock::new(HashMap::new())),
-            sender: Arc::new(sender),
-            receiver,
+            namespaced,
+            version,
+            data: RwLock::new(data),
+            watchers: StdMutex::new(Watchers {
+                compacted: loaded_version,
+                ..Default::default()
+            }),
+            files,
+        }
+    }
+
+    /// store object, it is written to file first if store is persisted
+    async fn put(&self, data: &mut BTreeMap<String, Value>, key: &str, value: Value) -> Result<()> {
+        if let Some(files) = &self.files {
+            files.write(key, &value).await?;
+        }
+        data.insert(key.to_owned(), value);
+        Ok(())
+    }
+
+    /// remove object deleted at version, file is removed first if store is persisted
+    async fn take(
+        &self,
+        data: &mut BTreeMap<String, Value>,
+        key: &str,
+        version: u64,
+    ) -> Result<Option<Value>> {
+        if let Some(files) = &self.files {
+            files.remove(key, version).await?;
+        }
+        Ok(data.remove(key))
+    }
+
+    /// version for next change, must be called while holding data write lock
+    fn next_version(&self) -> u64 {
+        self.version.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// true if watcher of namespace should receive change in namespace
+    fn is_watching(&self, watched: Option<&str>, namespace: &str) -> bool {
+        !self.namespaced || watched.is_none_or(|watched| watched == namespace)
+    }
+
+    /// key for object, namespace is ignored for cluster scoped objects
+    fn key<M: K8Meta>(&self, metadata: &M) -> String {
+        let namespace = if self.namespaced {
+            metadata.namespace()
+        } else {
+            ""
+        };
+        object_key(namespace, metadata.name())
+    }
+
+    /// namespace to filter on, empty namespace means all namespaces
+    fn namespace_filter(&self, namespace: NameSpace) -> Option<String> {
+        match namespace {
+            NameSpace::Named(namespace) if self.namespaced && !namespace.is_empty() => {
+                Some(namespace)
+            }
+            _ => None,
         }
     }
-}
 
-impl SpecStore {
     async fn get<S>(&self, key: &str) -> anyhow::Result<Option<S>>
     where
         S: DeserializeOwned,
@@ -71,99 +183,514 @@ impl SpecStore {
         Ok(serde_yaml::from_value(output)?)
     }
 
-    async fn insert<S>(&self, key: String, mut k8_obj: K8Obj<S>) -> anyhow::Result<()>
+    /// add new object, fails if object already exists
+    async fn create<S>(&self, mut k8_obj: K8Obj<S>) -> anyhow::Result<K8Obj<S>>
     where
-        S: Serialize + Spec + Clone + std::fmt::Debug,
+        S: Spec,
     {
+        let key = self.key(&k8_obj.metadata);
         let mut lock = self.data.write().await;
 
-        let maybe_old = lock.get(&key);
+        if lock.contains_key(&key) {
+            return Err(already_exists::<S>(&k8_obj.metadata.name));
+        }
 
-        let watch: K8Watch<S> = if let Some(old_version) = maybe_old {
-            let old_k8_obj: K8Obj<S> = serde_yaml::from_value(old_version.clone())?;
-            let old_resource_version = old_k8_obj
-                .metadata
-                .resource_version
-                .parse::<i32>()
-                .unwrap_or_default();
-            k8_obj.metadata.resource_version = (old_resource_version + 1).to_string();
+        let version = self.next_version();
+        k8_obj.metadata.resource_version = version.to_string();
+        k8_obj.metadata.uid = new_uid();
+        k8_obj.metadata.creation_timestamp = now();
+        k8_obj.metadata.generation = Some(1);
 
-            K8Watch::MODIFIED(k8_obj.clone())
-        } else {
-            K8Watch::ADDED(k8_obj.clone())
+        self.put(&mut lock, &key, serde_yaml::to_value(&k8_obj)?)
+            .await?;
+
+        self.notify(
+            version,
+            &k8_obj.metadata.namespace,
+            serde_yaml::to_value(K8Watch::ADDED(k8_obj.clone()))?,
+        );
+
+        Ok(k8_obj)
+    }
+
+    async fn insert<S>(&self, k8_obj: K8Obj<S>) -> anyhow::Result<K8Obj<S>>
+    where
+        S: Spec,
+    {
+        self.insert_if_version(k8_obj, None).await
+    }
+
+    /// replace existing object, if version is set, it must match version of stored object
+    async fn insert_if_version<S>(
+        &self,
+        mut k8_obj: K8Obj<S>,
+        version: Option<&str>,
+    ) -> anyhow::Result<K8Obj<S>>
+    where
+        S: Spec,
+    {
+        let key = self.key(&k8_obj.metadata);
+        let mut lock = self.data.write().await;
+
+        let Some(old_value) = lock.get(&key) else {
+            return Err(not_found::<S>(&k8_obj.metadata.name));
         };
+        let old_k8_obj: K8Obj<S> = serde_yaml::from_value(old_value.clone())?;
+        if version.is_some_and(|version| version != old_k8_obj.metadata.resource_version) {
+            return Err(conflict::<S>(&k8_obj.metadata.name));
+        }
 
-        let value = serde_yaml::to_value(k8_obj)?;
+        let version = self.next_version();
+        server_fields(&old_k8_obj, &mut k8_obj, version)?;
 
-        lock.insert(key, value);
+        self.put(&mut lock, &key, serde_yaml::to_value(&k8_obj)?)
+            .await?;
 
-        drop(lock);
+        self.notify(
+            version,
+            &k8_obj.metadata.namespace,
+            serde_yaml::to_value(K8Watch::MODIFIED(k8_obj.clone()))?,
+        );
 
-        let watch_value = serde_yaml::to_value(watch)?;
+        Ok(k8_obj)
+    }
 
-        let _ = self.sender.send(watch_value).await;
+    /// change stored object as json under lock, if version is set, it must match version of stored object.
+    /// returns None if object doesn't exist
+    async fn modify<S, F>(
+        &self,
+        key: &str,
+        version: Option<&str>,
+        change: F,
+    ) -> anyhow::Result<Option<K8Obj<S>>>
+    where
+        S: Spec,
+        F: FnOnce(&mut serde_json::Value) -> anyhow::Result<()>,
+    {
+        let mut lock = self.data.write().await;
 
-        Ok(())
+        let Some(old_value) = lock.get(key) else {
+            return Ok(None);
+        };
+        let old_k8_obj: K8Obj<S> = serde_yaml::from_value(old_value.clone())?;
+        if version.is_some_and(|version| version != old_k8_obj.metadata.resource_version) {
+            return Err(conflict::<S>(&old_k8_obj.metadata.name));
+        }
+        let old_json = serde_json::to_value(&old_k8_obj)?;
+
+        let mut json = old_json.clone();
+        change(&mut json)?;
+        if json == old_json {
+            return Ok(Some(old_k8_obj));
+        }
+
+        let mut k8_obj: K8Obj<S> = serde_json::from_value(json)?;
+        let version = self.next_version();
+        server_fields(&old_k8_obj, &mut k8_obj, version)?;
+
+        self.put(&mut lock, key, serde_yaml::to_value(&k8_obj)?)
+            .await?;
+
+        self.notify(
+            version,
+            &k8_obj.metadata.namespace,
+            serde_yaml::to_value(K8Watch::MODIFIED(k8_obj.clone()))?,
+        );
+
+        Ok(Some(k8_obj))
     }
 
-    async fn items<S>(&self) -> anyhow::Result<Vec<K8Obj<S>>>
+    /// items in namespace ordered by key, all items if namespace is not set.
+    /// returns current version with items
+    /// items continuing list made at list version, which fails with 410 if store has changed since
+    async fn items<S>(
+        &self,
+        namespace: Option<&str>,
+        start_after: Option<&str>,
+        list_version: Option<u64>,
+    ) -> anyhow::Result<(u64, Vec<(String, K8Obj<S>)>)>
     where
         S: Spec,
     {
+        let prefix = namespace.map(|namespace| object_key(namespace, ""));
         let lock = self.data.read().await;
-        let items: Result<Vec<K8Obj<S>>, _> = lock
-            .values()
-            .map(|value| serde_yaml::from_value(value.clone()))
+        if let Some(list_version) = list_version {
+            if self.changed_version() > list_version {
+                return Err(K8Error::from(continue_expired()).into_anyhow());
+            }
+        }
+        let version = self.version.load(Ordering::SeqCst);
+        let items: Result<Vec<(String, K8Obj<S>)>, serde_yaml::Error> = lock
+            .iter()
+            .filter(|(key, _)| {
+                prefix
+                    .as_deref()
+                    .is_none_or(|prefix| key.starts_with(prefix))
+                    && start_after.is_none_or(|start_after| key.as_str() > start_after)
+            })
+            .map(|(key, value)| Ok((key.clone(), serde_yaml::from_value(value.clone())?)))
             .collect();
 
-        Ok(items?)
+        Ok((version, items?))
+    }
+
+    /// delete object with uid.
+    /// object with finalizers or deleted in foreground is only marked for deletion and returned.
+    /// returns None if object is removed or doesn't exist
+    async fn delete(&self, key: &str, uid: &str, foreground: bool) -> Result<Option<Value>> {
+        let mut lock = self.data.write().await;
+        let Some(metadata) = stored_metadata(&lock, key, uid)? else {
+            return Ok(None);
+        };
+
+        if metadata.deletion_timestamp.is_none() && metadata.finalizers.is_empty() && !foreground {
+            self.remove_locked(&mut lock, key).await?;
+            return Ok(None);
+        }
+
+        let updated = self
+            .update_metadata_locked(&mut lock, key, |metadata| {
+                if metadata.deletion_timestamp.is_none() {
+                    metadata.deletion_timestamp = Some(now());
+                    if foreground {
+                        metadata.finalizers.push(FOREGROUND_DELETION.to_owned());
+                    }
+                }
+            })
+            .await?;
+        Ok(updated)
     }
 
-    async fn remove<S>(&self, key: &str) -> Result<Option<Value>>
+    /// remove object with uid, regardless of finalizers
+    async fn remove(&self, key: &str, uid: &str) -> Result<Option<Value>> {
+        let mut lock = self.data.write().await;
+        if stored_metadata(&lock, key, uid)?.is_none() {
+            return Ok(None);
+        }
+        self.remove_locked(&mut lock, key).await
+    }
+
+    /// change metadata of object with uid
+    async fn update_metadata<F>(&self, key: &str, uid: &str, change: F) -> Result<Option<Value>>
     where
-        S: Spec,
+        F: FnOnce(&mut ObjectMeta) + Send,
     {
         let mut lock = self.data.write().await;
-        let Some(value) = lock.remove(key) else {
+        if stored_metadata(&lock, key, uid)?.is_none() {
+            return Ok(None);
+        }
+        self.update_metadata_locked(&mut lock, key, change).await
+    }
+
+    async fn remove_locked(
+        &self,
+        data: &mut BTreeMap<String, Value>,
+        key: &str,
+    ) -> Result<Option<Value>> {
+        if !data.contains_key(key) {
+            return Ok(None);
+        }
+        let version = self.next_version();
+        let Some(value) = self.take(data, key, version).await? else {
             return Ok(None);
         };
 
-        drop(lock);
+        let mut object: serde_json::Value = serde_json::to_value(&value)?;
+        object["metadata"]["resourceVersion"] = version.to_string().into();
 
-        let k8_obj: K8Obj<S> = serde_yaml::from_value(value.clone())?;
+        let namespace = object["metadata"]["namespace"]
+            .as_str()
+            .unwrap_or_default()
+            .to_owned();
+        self.notify(version, &namespace, watch_event("DELETED", object)?);
 
-        let watch: K8Watch<S> = K8Watch::DELETED(k8_obj);
+        Ok(Some(value))
+    }
 
-        let watch_value = serde_yaml::to_value(watch)?;
+    /// change metadata, version is changed and watchers are notified only if metadata is changed
+    async fn update_metadata_locked<F>(
+        &self,
+        data: &mut BTreeMap<String, Value>,
+        key: &str,
+        change: F,
+    ) -> Result<Option<Value>>
+    where
+        F: FnOnce(&mut ObjectMeta) + Send,
+    {
+        let Some(value) = data.get(key) else {
+            return Ok(None);
+        };
+        let mut object: serde_json::Value = serde_json::to_value(value)?;
+        let old_metadata = object["metadata"].take();
+        let mut metadata: ObjectMeta = serde_json::from_value(old_metadata.clone())?;
+        change(&mut metadata);
+        let new_metadata = serde_json::to_value(&metadata)?;
+        if new_metadata == old_metadata {
+            object["metadata"] = old_metadata;
+            return Ok(Some(value.clone()));
+        }
 
-        let _ = self.sender.send(watch_value).await;
+        let version = self.next_version();
+        object["metadata"] = new_metadata;
+        object["metadata"]["resourceVersion"] = version.to_string().into();
+
+        let value = serde_yaml::to_value(&object)?;
+        self.put(data, key, value.clone()).await?;
+        self.notify(
+            version,
+            &metadata.namespace,
+            watch_event("MODIFIED", object)?,
+        );
 
         Ok(Some(value))
     }
 
-    fn watch_stream<S>(&self) -> BoxStream<'static, TokenStreamResult<S>>
+    /// metadata of all objects with their keys
+    async fn metadata(&self) -> Result<Vec<(String, ObjectMeta)>> {
+        let lock = self.data.read().await;
+        lock.iter()
+            .map(|(key, value)| {
+                let metadata = value.get("metadata").cloned().unwrap_or_default();
+                Ok((key.clone(), serde_yaml::from_value(metadata)?))
+            })
+            .collect()
+    }
+
+    /// version of newest change of this store
+    fn changed_version(&self) -> u64 {
+        let watchers = self.watchers.lock().unwrap_or_else(PoisonError::into_inner);
+        watchers
+            .history
+            .back()
+            .map_or(watchers.compacted, |change| change.version)
+    }
+
+    /// register watcher, it receives changes made after version or after this call if version is not set.
+    /// returns None if changes after version are no longer in history
+    fn subscribe(&self, namespace: Option<String>, since: Option<u64>) -> Option<Receiver<Value>> {
+        let (sender, receiver) = unbounded();
+        let mut watchers = self.watchers.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(since) = since {
+            if since < watchers.compacted {
+                return None;
+            }
+            for change in watchers.history.iter().filter(|change| {
+                change.version > since && self.is_watching(namespace.as_deref(), &change.namespace)
+            }) {
+                let _ = sender.try_send(change.event.clone());
+            }
+        }
+        watchers.watchers.push(Watcher { namespace, sender });
+        Some(receiver)
+    }
+
+    /// send change to watchers of namespace, watchers which are gone are removed.
+    /// called while holding data lock so watchers see changes in same order as they are made
+    fn notify(&self, version: u64, namespace: &str, event: Value) {
+        let mut watchers = self.watchers.lock().unwrap_or_else(PoisonError::into_inner);
+        watchers.watchers.retain(|watcher| {
+            if self.is_watching(watcher.namespace.as_deref(), namespace) {
+                watcher.sender.try_send(event.clone()).is_ok()
+            } else {
+                !watcher.sender.is_closed()
+            }
+        });
+
+        watchers.history.push_back(Change {
+            version,
+            namespace: namespace.to_owned(),
+            event,
+        });
+        if watchers.history.len() > HISTORY_LIMIT {
+            if let Some(change) = watchers.history.pop_front() {
+                watchers.compacted = change.version;
+            }
+        }
+    }
+
+    fn watch_stream<S>(
+        &self,
+        namespace: Option<String>,
+        since: Option<u64>,
+    ) -> BoxStream<'static, TokenStreamResult<S>>
     where
         S: Spec + 'static,
         S::Status: 'static,
         S::Header: 'static,
     {
-        self.receiver
-            .clone()
-            .map(|f| {
-                Ok(vec![
-                    serde_yaml::from_value::<K8Watch<S>>(f.clone()).map_err(|err| err.into())
-                ])
-            })
-            .boxed()
+        match self.subscribe(namespace, since) {
+            Some(receiver) => receiver
+                .map(|f| {
+                    Ok(vec![
+                        serde_yaml::from_value::<K8Watch<S>>(f).map_err(|err| err.into())
+                    ])
+                })
+                .boxed(),
+            None => {
+                let status = gone(since.unwrap_or_default());
+                stream::once(async { Ok(vec![Ok(K8Watch::ERROR(status))]) }).boxed()
+            }
+        }
+    }
+}
+
+/// metadata of stored object if it has uid
+fn stored_metadata(
+    data: &BTreeMap<String, Value>,
+    key: &str,
+    uid: &str,
+) -> Result<Option<ObjectMeta>> {
+    let Some(metadata) = data.get(key).and_then(|value| value.get("metadata")) else {
+        return Ok(None);
+    };
+    let metadata: ObjectMeta = serde_yaml::from_value(metadata.clone())?;
+    Ok((metadata.uid == uid).then_some(metadata))
+}
+
+/// watch event for untyped object, same as serialized [K8Watch]
+fn watch_event(event_type: &str, object: serde_json::Value) -> Result<Value> {
+    Ok(serde_yaml::to_value(
+        serde_json::json!({ "type": event_type, "object": object }),
+    )?)
+}
+
+fn now() -> String {
+    humantime::format_rfc3339_seconds(SystemTime::now()).to_string()
+}
+
+/// keep fields which are set by server from stored object.
+/// generation is incremented when spec changes
+fn server_fields<S: Spec>(old: &K8Obj<S>, new: &mut K8Obj<S>, version: u64) -> Result<()> {
+    new.metadata.name = old.metadata.name.clone();
+    new.metadata.namespace = old.metadata.namespace.clone();
+    new.metadata.uid = old.metadata.uid.clone();
+    new.metadata.creation_timestamp = old.metadata.creation_timestamp.clone();
+    new.metadata.generation = old.metadata.generation;
+    new.metadata.deletion_timestamp = old.metadata.deletion_timestamp.clone();
+    if serde_json::to_value(&old.spec)? != serde_json::to_value(&new.spec)? {
+        new.metadata.generation = Some(old.metadata.generation.unwrap_or_default() + 1);
+    }
+    new.metadata.resource_version = version.to_string();
+    Ok(())
+}
+
+/// random uid in UUID v4 format
+fn new_uid() -> String {
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!(
+        "{}-{}-4{}-{:x}{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[13..16],
+        8 | (bytes[8] & 0x3),
+        &hex[17..20],
+        &hex[20..32]
+    )
+}
+
+fn not_found<S: Spec>(name: &str) -> anyhow::Error {
+    K8Error::from(MetaStatus {
+        api_version: "v1".to_owned(),
+        code: Some(404),
+        details: None,
+        kind: "Status".to_owned(),
+        message: Some(format!(
+            "{} \"{}\" not found",
+            S::metadata().names.plural,
+            name
+        )),
+        reason: Some("NotFound".to_owned()),
+        status: StatusEnum::FAILURE,
+    })
+    .into_anyhow()
+}
+
+fn already_exists<S: Spec>(name: &str) -> anyhow::Error {
+    K8Error::from(MetaStatus {
+        api_version: "v1".to_owned(),
+        code: Some(409),
+        details: None,
+        kind: "Status".to_owned(),
+        message: Some(format!(
+            "{} \"{}\" already exists",
+            S::metadata().names.plural,
+            name
+        )),
+        reason: Some("AlreadyExists".to_owned()),
+        status: StatusEnum::FAILURE,
+    })
+    .into_anyhow()
+}
+
+fn conflict<S: Spec>(name: &str) -> anyhow::Error {
+    K8Error::from(MetaStatus {
+        api_version: "v1".to_owned(),
+        code: Some(409),
+        details: None,
+        kind: "Status".to_owned(),
+        message: Some(format!(
+            "Operation cannot be fulfilled on {} \"{}\": the object has been modified",
+            S::metadata().names.plural,
+            name
+        )),
+        reason: Some("Conflict".to_owned()),
+        status: StatusEnum::FAILURE,
+    })
+    .into_anyhow()
+}
+
+fn gone(version: u64) -> MetaStatus {
+    MetaStatus {
+        api_version: "v1".to_owned(),
+        code: Some(410),
+        details: None,
+        kind: "Status".to_owned(),
+        message: Some(format!("too old resource version: {version}")),
+        reason: Some("Expired".to_owned()),
+        status: StatusEnum::FAILURE,
+    }
+}
+
+fn continue_expired() -> MetaStatus {
+    MetaStatus {
+        api_version: "v1".to_owned(),
+        code: Some(410),
+        details: None,
+        kind: "Status".to_owned(),
+        message: Some(
+            "The provided continue parameter is too old to display a consistent list result"
+                .to_owned(),
+        ),
+        reason: Some("Expired".to_owned()),
+        status: StatusEnum::FAILURE,
     }
 }
 
 /// In-memory implementation of MetadataClient
-/// This is used for testing and in also in contexts where we don't have Kubernetes and
-/// there is not need to persist data
-#[derive(Debug, Default)]
+/// This is used for testing and in also in contexts where we don't have Kubernetes.
+/// Objects can be persisted in directory, see [MemoryClient::with_directory]
+#[derive(Debug)]
 pub struct MemoryClient {
-    data: Mutex<HashMap<String, Arc<SpecStore>>>,
+    data: StdMutex<HashMap<String, Arc<SpecStore>>>,
+    version: Arc<AtomicU64>,
+    directory: Option<PathBuf>,
+    /// objects loaded from directory by kind, moved to store when it is created
+    loaded: StdMutex<HashMap<String, BTreeMap<String, Value>>>,
+    /// newest version of loaded objects
+    loaded_version: u64,
+}
+
+impl Default for MemoryClient {
+    fn default() -> Self {
+        Self {
+            data: StdMutex::new(HashMap::new()),
+            version: Arc::new(AtomicU64::new(INITIAL_VERSION)),
+            directory: None,
+            loaded: StdMutex::new(HashMap::new()),
+            loaded_version: 0,
+        }
+    }
 }
 
 impl MemoryClient {
@@ -171,29 +698,134 @@ impl MemoryClient {
         Arc::new(Self::default())
     }
 
-    async fn get_store<S: Spec>(&self) -> Arc<SpecStore> {
-        let kind: String = S::kind();
-        let mut stores = self.data.lock().await;
-        if let Some(store) = stores.get(&kind) {
-            store.clone()
-        } else {
-            let store = Arc::new(SpecStore::default());
-            stores.insert(kind, store.clone());
-            store
-        }
+    /// Client which keeps objects in directory, so they survive restart.
+    /// Objects already in directory are loaded.
+    /// Watches can't be resumed from version before restart, they fail with 410 status
+    pub fn with_directory(directory: impl Into<PathBuf>) -> Result<Self> {
+        let directory = directory.into();
+        let loaded = files::load_all(&directory)?;
+        let loaded_version = loaded
+            .values()
+            .flat_map(|objects| objects.values())
+            .filter_map(|value| {
+                value
+                    .get("metadata")?
+                    .get("resourceVersion")?
+                    .as_str()?
+                    .parse::<u64>()
+                    .ok()
+            })
+            .max()
+            .unwrap_or_default()
+            .max(files::load_version(&directory)?);
+        debug!(directory = %directory.display(), loaded_version, "loaded objects");
+
+        Ok(Self {
+            data: StdMutex::new(HashMap::new()),
+            version: Arc::new(AtomicU64::new(loaded_version.max(INITIAL_VERSION))),
+            directory: Some(directory),
+            loaded: StdMutex::new(loaded),
+            loaded_version,
+        })
+    }
+
+    fn get_store<S: Spec>(&self) -> Arc<SpecStore> {
+        self.data
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(S::kind())
+            .or_insert_with(|| {
+                let files = self
+                    .directory
+                    .as_ref()
+                    .map(|directory| Files::new(directory, &S::kind()));
+                let data = self
+                    .loaded
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .remove(&S::kind())
+                    .unwrap_or_default();
+                Arc::new(SpecStore::new(
+                    S::NAME_SPACED,
+                    self.version.clone(),
+                    files,
+                    data,
+                    self.loaded_version,
+                ))
+            })
+            .clone()
     }
 
+    /// list items in all namespaces
     pub async fn retrieve_items_inner<S: Spec>(&self) -> Result<K8List<S>> {
-        let store = self.get_store::<S>().await;
-        let items: Vec<K8Obj<S>> = store.items().await?;
+        self.list(NameSpace::All, None).await
+    }
+
+    /// collect object if it is being deleted, change may have removed its last finalizer
+    async fn finalized<S: Spec>(&self, k8_obj: K8Obj<S>) -> Result<K8Obj<S>> {
+        if k8_obj.metadata.deletion_timestamp.is_some() {
+            self.collect_garbage().await?;
+        }
+        Ok(k8_obj)
+    }
+
+    /// list up to limit items starting after continue token.
+    /// continue token carries version of first list so all chunks report same version.
+    /// only latest version is kept, so requested resource version is ignored and
+    /// continuing fails with 410 status if objects have changed since first list
+    pub(crate) async fn list<S: Spec>(
+        &self,
+        namespace: NameSpace,
+        option: Option<ListArg>,
+    ) -> Result<K8List<S>> {
+        let store = self.get_store::<S>();
+        let option = option.unwrap_or_default();
+        let (limit, continu) = (option.limit, option.continu.clone());
+        let selector = Selector::parse(
+            option.label_selector.as_deref(),
+            option.field_selector.as_deref(),
+        )?;
+        let (list_version, start_after) = match continu.as_deref() {
+            Some(token) => {
+                let (version, key) = token
+                    .split_once(':')
+                    .and_then(|(version, key)| Some((version.parse::<u64>().ok()?, key)))
+                    .ok_or_else(|| anyhow!("invalid continue token: {}", token))?;
+                (Some(version), Some(key.to_owned()))
+            }
+            None => (None, None),
+        };
+
+        let namespace = store.namespace_filter(namespace);
+        let (version, items) = store
+            .items::<S>(namespace.as_deref(), start_after.as_deref(), list_version)
+            .await?;
+        let resource_version = list_version.unwrap_or(version).to_string();
+
+        // limit of 0 means no limit
+        let limit = limit
+            .filter(|limit| *limit > 0)
+            .map_or(usize::MAX, |limit| limit as usize);
+        let mut matched = items.into_iter().filter(|(_, item)| selector.matches(item));
+        let page: Vec<(String, K8Obj<S>)> = matched.by_ref().take(limit).collect();
+        let remaining = matched.count();
+        let (_continue, remaining_item_count) = match page.last() {
+            Some((key, _)) if remaining > 0 => (
+                Some(format!("{resource_version}:{key}")),
+                Some(remaining as i64),
+            ),
+            _ => (None, None),
+        };
+
         Ok(K8List {
             api_version: S::api_version(),
             kind: S::kind(),
             metadata: ListMetadata {
-                _continue: None,
-                resource_version: "0".to_owned(),
+                _continue,
+                resource_version,
+                remaining_item_count,
             },
-            items,
+            items: page.into_iter().map(|(_, item)| item).collect(),
         })
     }
 }
@@ -206,50 +838,93 @@ impl MetadataClient for MemoryClient {
         S: Spec,
         M: K8Meta + Send + Sync,
     {
-        let store = self.get_store::<S>().await;
-
-        let name: String = metadata.name().to_owned();
-        store.get::<K8Obj<S>>(&name).await
+        let store = self.get_store::<S>();
+        store.get::<K8Obj<S>>(&store.key(metadata)).await
     }
 
     async fn retrieve_items_with_option<S, N>(
         &self,
-        _namespace: N,
-        _option: Option<ListArg>,
+        namespace: N,
+        option: Option<ListArg>,
     ) -> Result<K8List<S>>
     where
         S: Spec,
         N: Into<NameSpace> + Send + Sync,
     {
-        self.retrieve_items_inner().await
+        self.list(namespace.into(), option).await
     }
 
     fn retrieve_items_in_chunks<'a, S, N>(
         self: Arc<Self>,
-        _namespace: N,
-        _limit: u32,
-        _option: Option<ListArg>,
+        namespace: N,
+        limit: u32,
+        option: Option<ListArg>,
     ) -> BoxStream<'a, K8List<S>>
     where
         S: Spec + 'static,
         N: Into<NameSpace> + Send + Sync + 'static,
     {
-        futures_util::stream::pending().boxed()
+        let namespace = namespace.into();
+        // state is continue token of next chunk, None when done
+        stream::unfold(Some(None), move |continu| {
+            let client = self.clone();
+            let namespace = namespace.clone();
+            let option = ListArg {
+                limit: Some(limit),
+                ..option.clone().unwrap_or_default()
+            };
+            async move {
+                let option = ListArg {
+                    continu: continu?,
+                    ..option
+                };
+                match client.list::<S>(namespace, Some(option)).await {
+                    Ok(list) => {
+                        let next = list.metadata._continue.clone().map(Some);
+                        Some((list, next))
+                    }
+                    Err(err) => {
+                        error!("{}: error in list stream: {}", S::label(), err);
+                        None
+                    }
+                }
+            }
+        })
+        .boxed()
     }
 
+    /// objects with finalizers are only marked for deletion until finalizers are removed.
+    /// dependents are deleted or orphaned according to propagation policy, default is background
     async fn delete_item_with_option<S, M>(
         &self,
         metadata: &M,
-        _option: Option<DeleteOptions>,
+        option: Option<DeleteOptions>,
     ) -> Result<DeleteStatus<S>>
     where
         S: Spec,
         M: K8Meta + Send + Sync,
     {
-        let store = self.get_store::<S>().await;
-        let key = metadata.name();
+        let store = self.get_store::<S>();
+        let key = store.key(metadata);
+
+        let policy = option.and_then(|option| option.propagation_policy);
+        let status = match store.get::<K8Obj<S>>(&key).await? {
+            Some(k8_obj) => {
+                let uid = k8_obj.metadata.uid;
+                if matches!(policy, Some(PropogationPolicy::Orphan)) {
+                    self.orphan_dependents(&uid).await?;
+                }
+                let foreground = matches!(policy, Some(PropogationPolicy::Foreground));
+                let deleting = store.delete(&key, &uid, foreground).await?;
+                self.collect_garbage().await?;
+                deleting.map(serde_yaml::from_value).transpose()?
+            }
+            None => None,
+        };
 
-        store.remove::<S>(key).await?;
+        if let Some(k8_obj) = status {
+            return Ok(DeleteStatus::ForegroundDelete(k8_obj));
+        }
 
         Ok(DeleteStatus::Deleted(MetaStatus {
             api_version: S::api_version(),
@@ -257,7 +932,7 @@ impl MetadataClient for MemoryClient {
             details: None,
             kind: S::kind(),
             reason: None,
-            status: k8_types::StatusEnum::SUCCESS,
+            status: StatusEnum::SUCCESS,
             message: None,
         }))
     }
@@ -267,10 +942,9 @@ impl MetadataClient for MemoryClient {
     where
         S: Spec,
     {
-        let store = self.get_store::<S>().await;
-        let key = value.metadata.name.clone();
+        let store = self.get_store::<S>();
 
-        let mut k8_obj: K8Obj<S> = K8Obj::new(key.clone(), value.spec);
+        let mut k8_obj: K8Obj<S> = K8Obj::new(value.metadata.name.clone(), value.spec);
 
         let metadata = value.metadata;
 
@@ -284,9 +958,62 @@ impl MetadataClient for MemoryClient {
             ..Default::default()
         };
 
-        store.insert(key, k8_obj.clone()).await?;
+        store.create(k8_obj).await
+    }
 
-        Ok(k8_obj)
+    async fn replace_item<S>(&self, value: UpdatedK8Obj<S>) -> Result<K8Obj<S>>
+    where
+        S: Spec,
+    {
+        let store = self.get_store::<S>();
+        let key = store.key(&value.metadata);
+
+        let k8_value: Option<K8Obj<S>> = store.get(&key).await?;
+        let mut k8_obj = k8_value.ok_or_else(|| not_found::<S>(&value.metadata.name))?;
+
+        let metadata = value.metadata;
+        let version = (!metadata.resource_version.is_empty()).then_some(metadata.resource_version);
+        k8_obj.metadata.labels = metadata.labels;
+        k8_obj.metadata.annotations = metadata.annotations;
+        k8_obj.metadata.owner_references = metadata.owner_references;
+        k8_obj.metadata.finalizers = metadata.finalizers;
+        k8_obj.spec = value.spec;
+        k8_obj.header = value.header;
+
+        let k8_obj = store.insert_if_version(k8_obj, version.as_deref()).await?;
+        self.finalized(k8_obj).await
+    }
+
+    /// fields are not tracked by manager, so there is never conflict.
+    /// labels and annotations are merged, spec is replaced
+    async fn server_side_apply<S>(
+        &self,
+        value: InputK8Obj<S>,
+        _options: ApplyOptions,
+    ) -> Result<K8Obj<S>>
+    where
+        S: Spec,
+    {
+        let store = self.get_store::<S>();
+
+        let Some(mut k8_obj) = store.get::<K8Obj<S>>(&store.key(&value.metadata)).await? else {
+            return self.create_item(value).await;
+        };
+
+        let metadata = value.metadata;
+        k8_obj.metadata.labels.extend(metadata.labels);
+        k8_obj.metadata.annotations.extend(metadata.annotations);
+        if !metadata.owner_references.is_empty() {
+            k8_obj.metadata.owner_references = metadata.owner_references;
+        }
+        if !metadata.finalizers.is_empty() {
+            k8_obj.metadata.finalizers = metadata.finalizers;
+        }
+        k8_obj.spec = value.spec;
+        k8_obj.header = value.header;
+
+        let k8_obj = store.insert(k8_obj).await?;
+        self.finalized(k8_obj).await
     }
 
     /// update status
@@ -294,74 +1021,105 @@ impl MetadataClient for MemoryClient {
     where
         S: Spec,
     {
-        let store = self.get_store::<S>().await;
+        let store = self.get_store::<S>();
 
-        let key = value.metadata.name.clone();
+        let key = store.key(&value.metadata);
         debug!(key,?value.status,"start updating status");
 
-        let k8_value: Option<K8Obj<S>> = store.get(&key).await?;
-        let k8_value = k8_value.ok_or(ObjectKeyNotFound::new(key.clone()))?;
-
-        let k8_obj = k8_value.set_status(value.status.clone());
-        debug!(key,?value.status,"overwrite set");
+        let version = &value.metadata.resource_version;
+        let version = (!version.is_empty()).then_some(version.as_str());
+        let status = serde_json::to_value(&value.status)?;
 
-        store.insert(key, k8_obj.clone()).await?;
+        let k8_obj = store
+            .modify::<S, _>(&key, version, |k8_obj| {
+                k8_obj["status"] = status;
+                Ok(())
+            })
+            .await?
+            .ok_or_else(|| not_found::<S>(&value.metadata.name))?;
 
         debug!("done");
 
-        Ok(k8_obj)
+        self.finalized(k8_obj).await
     }
 
     /// patch existing with spec
     async fn patch<S, M>(
         &self,
-        _metadata: &M,
-        _patch: &serde_json::Value,
-        _merge_type: PatchMergeType,
+        metadata: &M,
+        patch: &serde_json::Value,
+        merge_type: PatchMergeType,
     ) -> Result<K8Obj<S>>
     where
         S: Spec,
         M: K8Meta + Display + Send + Sync,
     {
-        // TODO: implement or move to another trait
-        unimplemented!()
+        debug!(%metadata, "patching");
+        let store = self.get_store::<S>();
+        let key = store.key(metadata);
+
+        let k8_obj = store
+            .modify::<S, _>(&key, None, |value| apply_patch(value, patch, &merge_type))
+            .await?
+            .ok_or_else(|| not_found::<S>(metadata.name()))?;
+        self.finalized(k8_obj).await
     }
 
     /// patch status
     async fn patch_status<S, M>(
         &self,
-        _metadata: &M,
-        _patch: &serde_json::Value,
-        _merge_type: PatchMergeType,
+        metadata: &M,
+        patch: &serde_json::Value,
+        merge_type: PatchMergeType,
     ) -> Result<K8Obj<S>>
     where
         S: Spec,
         M: K8Meta + Display + Send + Sync,
     {
-        // TODO: implement or move to another trait
-        unimplemented!()
+        self.patch_subresource(metadata, String::from("/status"), patch, merge_type)
+            .await
     }
 
+    /// only status subresource is supported. patch is applied to whole object but only status is kept
     async fn patch_subresource<S, M>(
         &self,
-        _metadata: &M,
-        _subresource: String,
-        _patch: &serde_json::Value,
-        _merge_type: PatchMergeType,
+        metadata: &M,
+        subresource: String,
+        patch: &serde_json::Value,
+        merge_type: PatchMergeType,
     ) -> Result<K8Obj<S>>
     where
         S: Spec,
         M: K8Meta + Display + Send + Sync,
     {
-        // TODO: implement or move to another trait
-        unimplemented!()
+        if subresource.trim_start_matches('/') != "status" {
+            return Err(anyhow!("subresource not supported: {}", subresource));
+        }
+        debug!(%metadata, "patching status");
+        let store = self.get_store::<S>();
+        let key = store.key(metadata);
+
+        let k8_obj = store
+            .modify::<S, _>(&key, None, |value| {
+                let mut patched = value.clone();
+                apply_patch(&mut patched, patch, &merge_type)?;
+                if let Some(status) = patched.get_mut("status") {
+                    value["status"] = status.take();
+                }
+                Ok(())
+            })
+            .await?
+            .ok_or_else(|| not_found::<S>(metadata.name()))?;
+        self.finalized(k8_obj).await
     }
 
-    /// stream items since resource versions
+    /// stream changes made after resource version.
+    /// if version is not set or is "0", only changes made after this call are streamed.
+    /// if changes since version are no longer kept, single ERROR event with 410 status is streamed
     fn watch_stream_since<S, N>(
         &self,
-        _namespace: N,
-        _resource_version: Option<String>,
+        namespace: N,
+        resource_version: Option<String>,
     ) -> BoxStream<'_, TokenStreamResult<S>>
     where
         S: Spec + 'static,
@@ -369,17 +1127,18 @@ impl MetadataClient for MemoryClient {
         S::Header: 'static,
         N: Into<NameSpace>,
     {
-        let ft_stream = async move {
-            let kind: String = S::kind();
-
-            let mut lock = self.data.lock().await;
-            let store = lock.entry(kind).or_default();
-            let st2 = store.clone();
-            drop(lock);
-            st2.watch_stream()
+        let since = match resource_version.as_deref() {
+            None | Some("") | Some("0") => None,
+            Some(version) => match version.parse::<u64>() {
+                Ok(version) => Some(version),
+                Err(_) => {
+                    let err = anyhow!("invalid resource version: {}", version);
+                    return stream::once(async { Err(err) }).boxed();
+                }
+            },
         };
-
-        ft_stream.flatten_stream().boxed()
+        let store = self.get_store::<S>();
+        store.watch_stream(store.namespace_filter(namespace.into()), since)
     }
 }
 
@@ -449,9 +1208,9 @@ mod test {
         assert_eq!(k8_obj.status.value, 0);
 
         // test update
-        let my_spec = MySpec { value: 15 };
-        let value = K8Obj::new("test".to_owned(), my_spec).as_input();
-        client.create_item(value).await.expect("failed to create");
+        let mut value = k8_obj.as_update();
+        value.spec = MySpec { value: 15 };
+        client.replace_item(value).await.expect("failed to update");
 
         let next_value = stream.next().await;
         let values_diffs = next_value.expect("value added");
@@ -513,4 +1272,624 @@ mod test {
             panic!("expected deleted");
         };
     }
+
+    #[fluvio_future::test]
+    async fn test_memory_patch() {
+        use futures_util::StreamExt;
+        use k8_metadata_client::{ApplyResult, PatchMergeType};
+        use serde_json::json;
+
+        let client = MemoryClient::new_shared();
+        let mut stream = client.watch_stream_since::<MySpec, String>("".into(), None);
+
+        let created = client
+            .create_item(K8Obj::new("test", MySpec { value: 10 }).as_input())
+            .await
+            .expect("create");
+        stream.next().await.expect("added").expect("events");
+
+        // apply on existing object is patched
+        let result = client
+            .apply(K8Obj::new("test", MySpec { value: 11 }).as_input())
+            .await
+            .expect("apply");
+        let ApplyResult::Patched(patched) = result else {
+            panic!("expected patched");
+        };
+        assert_eq!(patched.spec.value, 11);
+        assert_ne!(
+            patched.metadata.resource_version,
+            created.metadata.resource_version
+        );
+        let mut events = stream.next().await.expect("modified").expect("events");
+        let Ok(K8Watch::MODIFIED(k8_obj)) = events.remove(0) else {
+            panic!("expected modified");
+        };
+        assert_eq!(k8_obj.spec.value, 11);
+
+        let patched: K8Obj<MySpec> = client
+            .patch(
+                &patched.metadata.as_input(),
+                &json!([{"op": "replace", "path": "/spec/value", "value": 12}]),
+                PatchMergeType::Json,
+            )
+            .await
+            .expect("json patch");
+        assert_eq!(patched.spec.value, 12);
+
+        // only status is changed by status patch
+        let patched: K8Obj<MySpec> = client
+            .patch_status(
+                &patched.metadata.as_input(),
+                &json!({"spec": {"value": 0}, "status": {"value": 5}}),
+                PatchMergeType::JsonMerge,
+            )
+            .await
+            .expect("status patch");
+        assert_eq!(patched.spec.value, 12);
+        assert_eq!(patched.status.value, 5);
+    }
+
+    #[fluvio_future::test]
+    async fn test_memory_namespaces() {
+        use futures_util::StreamExt;
+        use k8_metadata_client::{ListArg, NameSpace};
+        use k8_types::InputObjectMeta;
+
+        let client = MemoryClient::new_shared();
+        let mut stream = client.watch_stream_since::<MySpec, _>("ns2", None);
+
+        let mut input = K8Obj::new("test", MySpec { value: 1 }).as_input();
+        input.metadata = InputObjectMeta::named("test", "ns1");
+        input
+            .metadata
+            .labels
+            .insert("app".to_owned(), "one".to_owned());
+        client.create_item(input.clone()).await.expect("create");
+
+        // same name in other namespace is different object
+        input.metadata.namespace = "ns2".to_owned();
+        input
+            .metadata
+            .labels
+            .insert("app".to_owned(), "two".to_owned());
+        input.spec.value = 2;
+        client.create_item(input.clone()).await.expect("create");
+
+        let mut events = stream.next().await.expect("added").expect("events");
+        let Ok(K8Watch::ADDED(k8_obj)) = events.remove(0) else {
+            panic!("expected added");
+        };
+        assert_eq!(k8_obj.metadata.namespace, "ns2");
+        assert_eq!(k8_obj.spec.value, 2);
+
+        let obj = client
+            .retrieve_item::<MySpec, _>(&InputObjectMeta::named("test", "ns1"))
+            .await
+            .expect("retrieve")
+            .expect("exists");
+        assert_eq!(obj.spec.value, 1);
+
+        let list = client
+            .retrieve_items::<MySpec, _>("ns1")
+            .await
+            .expect("list");
+        assert_eq!(list.items.len(), 1);
+        let list = client
+            .retrieve_items::<MySpec, _>(NameSpace::All)
+            .await
+            .expect("list");
+        assert_eq!(list.items.len(), 2);
+
+        let list = client
+            .retrieve_items_with_option::<MySpec, _>(
+                NameSpace::All,
+                Some(ListArg {
+                    label_selector: Some("app in (two,three)".to_owned()),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .expect("list");
+        assert_eq!(list.items.len(), 1);
+        assert_eq!(list.items[0].metadata.namespace, "ns2");
+
+        let list = client
+            .retrieve_items_with_option::<MySpec, _>(
+                NameSpace::All,
+                Some(ListArg {
+                    field_selector: Some("metadata.namespace!=ns2".to_owned()),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .expect("list");
+        assert_eq!(list.items.len(), 1);
+        assert_eq!(list.items[0].metadata.namespace, "ns1");
+
+        // change in other namespace is not seen by watch
+        client
+            .delete_item::<MySpec, _>(&InputObjectMeta::named("test", "ns1"))
+            .await
+            .expect("delete");
+        client
+            .delete_item::<MySpec, _>(&InputObjectMeta::named("test", "ns2"))
+            .await
+            .expect("delete");
+        let mut events = stream.next().await.expect("deleted").expect("events");
+        let Ok(K8Watch::DELETED(k8_obj)) = events.remove(0) else {
+            panic!("expected deleted");
+        };
+        assert_eq!(k8_obj.metadata.namespace, "ns2");
+    }
+
+    #[fluvio_future::test]
+    async fn test_memory_chunks_and_versions() {
+        use futures_util::StreamExt;
+        use k8_metadata_client::{K8ErrorExt, ListArg, NameSpace};
+
+        let client = MemoryClient::new_shared();
+
+        let mut versions = vec![];
+        for value in 0..5 {
+            let created = client
+                .create_item(K8Obj::new(format!("test{value}"), MySpec { value }).as_input())
+                .await
+                .expect("create");
+            versions.push(
+                created
+                    .metadata
+                    .resource_version
+                    .parse::<u64>()
+                    .expect("version"),
+            );
+        }
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let chunks: Vec<_> = client
+            .clone()
+            .retrieve_items_in_chunks::<MySpec, _>(NameSpace::All, 2, None)
+            .collect()
+            .await;
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| chunk.items.len())
+                .collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.metadata.resource_version == versions[4].to_string()));
+        assert!(chunks[2].metadata._continue.is_none());
+
+        // list can't be continued after change
+        let option = ListArg {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let first = client
+            .retrieve_items_with_option::<MySpec, _>(NameSpace::All, Some(option.clone()))
+            .await
+            .expect("list");
+        let mut update = first.items[0].as_update();
+        update.spec = MySpec { value: 10 };
+        client.replace_item(update).await.expect("replace");
+        let err = client
+            .retrieve_items_with_option::<MySpec, _>(
+                NameSpace::All,
+                Some(ListArg {
+                    continu: first.metadata._continue,
+                    ..option
+                }),
+            )
+            .await
+            .expect_err("expired");
+        assert!(err.is_gone());
+
+        // only changes newer than version are replayed
+        let mut stream =
+            client.watch_stream_since::<MySpec, _>(NameSpace::All, Some(versions[2].to_string()));
+        for name in ["test3", "test4"] {
+            let mut events = stream.next().await.expect("replayed").expect("events");
+            let Ok(K8Watch::ADDED(k8_obj)) = events.remove(0) else {
+                panic!("expected added");
+            };
+            assert_eq!(k8_obj.metadata.name, name);
+        }
+
+        // version older than kept history is gone
+        for value in 0..super::HISTORY_LIMIT as i32 {
+            client
+                .create_item(K8Obj::new(format!("more{value}"), MySpec { value }).as_input())
+                .await
+                .expect("create");
+        }
+        let mut stream =
+            client.watch_stream_since::<MySpec, _>(NameSpace::All, Some(versions[0].to_string()));
+        let mut events = stream.next().await.expect("error").expect("events");
+        let Ok(K8Watch::ERROR(status)) = events.remove(0) else {
+            panic!("expected error");
+        };
+        assert_eq!(status.code, Some(410));
+    }
+
+    #[fluvio_future::test]
+    async fn test_memory_watch_from_empty_list() {
+        use futures_util::StreamExt;
+        use k8_metadata_client::NameSpace;
+
+        let client = MemoryClient::new_shared();
+
+        // version of empty list must not mean watch from now
+        let list = client
+            .retrieve_items::<MySpec, _>(NameSpace::All)
+            .await
+            .expect("list");
+        assert!(list.items.is_empty());
+        assert_ne!(list.metadata.resource_version, "0");
+
+        let mut stream = client
+            .watch_stream_since::<MySpec, _>(NameSpace::All, Some(list.metadata.resource_version));
+        client
+            .create_item(K8Obj::new("first".to_owned(), MySpec { value: 1 }).as_input())
+            .await
+            .expect("create");
+        let mut events = stream.next().await.expect("added").expect("events");
+        let Ok(K8Watch::ADDED(k8_obj)) = events.remove(0) else {
+            panic!("expected added");
+        };
+        assert_eq!(k8_obj.metadata.name, "first");
+    }
+
+    #[fluvio_future::test]
+    async fn test_memory_conflicts() {
+        use k8_metadata_client::K8ErrorExt;
+
+        let client = MemoryClient::new_shared();
+
+        let input = K8Obj::new("test", MySpec { value: 1 }).as_input();
+        let created = client.create_item(input.clone()).await.expect("create");
+        assert!(!created.metadata.uid.is_empty());
+        assert!(!created.metadata.creation_timestamp.is_empty());
+        assert_eq!(created.metadata.generation, Some(1));
+
+        let err = client.create_item(input).await.expect_err("exists");
+        assert!(err.is_already_exists());
+
+        // spec change increments generation
+        let mut update = created.as_update();
+        update.spec = MySpec { value: 2 };
+        let updated = client.replace_item(update.clone()).await.expect("replace");
+        assert_eq!(updated.metadata.uid, created.metadata.uid);
+        assert_eq!(updated.metadata.generation, Some(2));
+
+        let err = client.replace_item(update).await.expect_err("stale");
+        assert!(err.is_conflict());
+
+        let status_update = created.as_status_update(MySpecStatus { value: 5 });
+        let err = client
+            .update_status(&status_update)
+            .await
+            .expect_err("stale");
+        assert!(err.is_conflict());
+
+        // status change doesn't change generation
+        let status_update = updated.as_status_update(MySpecStatus { value: 5 });
+        let updated = client.update_status(&status_update).await.expect("status");
+        assert_eq!(updated.status.value, 5);
+        assert_eq!(updated.metadata.generation, Some(2));
+    }
+
+    #[fluvio_future::test]
+    async fn test_memory_retry_on_conflict() {
+        use k8_metadata_client::K8ErrorExt;
+        use k8_types::InputObjectMeta;
+
+        let client = MemoryClient::new_shared();
+        let created = client
+            .create_item(K8Obj::new("test", MySpec { value: 1 }).as_input())
+            .await
+            .expect("create");
+        let metadata = created.metadata.as_input();
+
+        // first attempt is made with stale version as if object was modified by other client
+        let mut attempts = 0;
+        let updated = client
+            .retry_on_conflict::<MySpec, _, _>(&metadata, |k8_obj| {
+                attempts += 1;
+                if attempts == 1 {
+                    k8_obj.metadata.resource_version = "0".to_owned();
+                }
+                k8_obj.spec.value += 1;
+            })
+            .await
+            .expect("update");
+        assert_eq!(attempts, 2);
+        assert_eq!(updated.spec.value, 2);
+
+        let mut attempts = 0;
+        let updated = client
+            .retry_status_on_conflict::<MySpec, _, _>(&metadata, |k8_obj| {
+                attempts += 1;
+                if attempts == 1 {
+                    k8_obj.metadata.resource_version = "0".to_owned();
+                }
+                k8_obj.status.value = 10;
+            })
+            .await
+            .expect("status");
+        assert_eq!(attempts, 2);
+        assert_eq!(updated.status.value, 10);
+        assert_eq!(updated.spec.value, 2);
+
+        // conflicts are retried limited number of times
+        let err = client
+            .retry_on_conflict::<MySpec, _, _>(&metadata, |k8_obj| {
+                k8_obj.metadata.resource_version = "0".to_owned();
+            })
+            .await
+            .expect_err("conflict");
+        assert!(err.is_conflict());
+
+        let err = client
+            .retry_on_conflict::<MySpec, _, _>(
+                &InputObjectMeta::named("none", &metadata.namespace),
+                |_| {},
+            )
+            .await
+            .expect_err("not found");
+        assert!(err.is_not_found());
+    }
+
+    #[fluvio_future::test]
+    async fn test_memory_garbage_collection() {
+        use k8_types::options::{DeleteOptions, PropogationPolicy};
+        use k8_types::{DeleteStatus, InputK8Obj};
+
+        let client = MemoryClient::new_shared();
+
+        async fn family(
+            client: &MemoryClient,
+            name: &str,
+            finalizer: Option<&str>,
+        ) -> (K8Obj<MySpec>, K8Obj<MySpec>) {
+            let parent = client
+                .create_item(K8Obj::new(name, MySpec::default()).as_input())
+                .await
+                .expect("parent");
+            let mut metadata = parent
+                .metadata
+                .make_child_input_metadata::<MySpec>(format!("{name}-child"));
+            metadata.finalizers.extend(finalizer.map(str::to_owned));
+            let child = client
+                .create_item(InputK8Obj::new(MySpec::default(), metadata))
+                .await
+                .expect("child");
+            (parent, child)
+        }
+
+        fn delete_option(policy: PropogationPolicy) -> Option<DeleteOptions> {
+            Some(DeleteOptions {
+                propagation_policy: Some(policy),
+                ..Default::default()
+            })
+        }
+
+        // background
+        let (parent, child) = family(&client, "background", None).await;
+        client
+            .delete_item::<MySpec, _>(&parent.metadata)
+            .await
+            .expect("delete");
+        let found = client
+            .retrieve_item::<MySpec, _>(&child.metadata)
+            .await
+            .expect("retrieve");
+        assert!(found.is_none());
+
+        // orphan
+        let (parent, child) = family(&client, "orphan", None).await;
+        client
+            .delete_item_with_option::<MySpec, _>(
+                &parent.metadata,
+                delete_option(PropogationPolicy::Orphan),
+            )
+            .await
+            .expect("delete");
+        let orphan = client
+            .retrieve_item::<MySpec, _>(&child.metadata)
+            .await
+            .expect("retrieve")
+            .expect("orphan");
+        assert!(orphan.metadata.owner_references.is_empty());
+
+        // foreground, child is held by finalizer
+        let (parent, child) = family(&client, "foreground", Some("test/hold")).await;
+        let status = client
+            .delete_item_with_option::<MySpec, _>(
+                &parent.metadata,
+                delete_option(PropogationPolicy::Foreground),
+            )
+            .await
+            .expect("delete");
+        let DeleteStatus::ForegroundDelete(deleting) = status else {
+            panic!("expected foreground delete");
+        };
+        assert!(deleting.metadata.deletion_timestamp.is_some());
+        let child = client
+            .retrieve_item::<MySpec, _>(&child.metadata)
+            .await
+            .expect("retrieve")
+            .expect("held by finalizer");
+        assert!(child.metadata.deletion_timestamp.is_some());
+        assert!(client
+            .retrieve_item::<MySpec, _>(&parent.metadata)
+            .await
+            .expect("retrieve")
+            .is_some());
+
+        // removing finalizer completes deletion of child and then parent
+        let mut update = child.as_update();
+        update.metadata.finalizers.clear();
+        client.replace_item(update).await.expect("replace");
+        for metadata in [&child.metadata, &parent.metadata] {
+            let found = client
+                .retrieve_item::<MySpec, _>(metadata)
+                .await
+                .expect("retrieve");
+            assert!(found.is_none());
+        }
+
+        // child with another owner only loses reference to owner deleted in foreground
+        let (parent, child) = family(&client, "shared", None).await;
+        let other = client
+            .create_item(K8Obj::new("other", MySpec::default()).as_input())
+            .await
+            .expect("other");
+        let mut update = child.as_update();
+        update
+            .metadata
+            .owner_references
+            .push(other.metadata.make_owner_reference::<MySpec>());
+        client.replace_item(update).await.expect("replace");
+        client
+            .delete_item_with_option::<MySpec, _>(
+                &parent.metadata,
+                delete_option(PropogationPolicy::Foreground),
+            )
+            .await
+            .expect("delete");
+        let child = client
+            .retrieve_item::<MySpec, _>(&child.metadata)
+            .await
+            .expect("retrieve")
+            .expect("kept by other owner");
+        assert!(child.metadata.deletion_timestamp.is_none());
+        assert_eq!(
+            child.metadata.owner_references,
+            vec![other.metadata.make_owner_reference::<MySpec>()]
+        );
+        assert!(client
+            .retrieve_item::<MySpec, _>(&parent.metadata)
+            .await
+            .expect("retrieve")
+            .is_none());
+    }
+
+    #[fluvio_future::test]
+    async fn test_memory_files() {
+        use futures_util::StreamExt;
+        use k8_metadata_client::NameSpace;
+        use k8_types::InputObjectMeta;
+
+        let directory =
+            std::env::temp_dir().join(format!("memory-client-{}", rand::random::<u32>()));
+
+        let client = MemoryClient::with_directory(&directory).expect("open");
+        let mut input = K8Obj::new("test", MySpec { value: 1 }).as_input();
+        input.metadata = InputObjectMeta::named("test", "ns1");
+        let created = client.create_item(input.clone()).await.expect("create");
+        input.metadata.name = "removed".to_owned();
+        let removed = client.create_item(input).await.expect("create");
+        client
+            .delete_item::<MySpec, _>(&removed.metadata)
+            .await
+            .expect("delete");
+
+        assert!(directory.join("myspec/ns1/test.yaml").is_file());
+        assert!(!directory.join("myspec/ns1/removed.yaml").exists());
+        drop(client);
+
+        // objects survive restart
+        let client = MemoryClient::with_directory(&directory).expect("open");
+        let list = client
+            .retrieve_items::<MySpec, _>(NameSpace::All)
+            .await
+            .expect("list");
+        assert_eq!(list.items.len(), 1);
+        let restored = &list.items[0];
+        assert_eq!(restored.metadata.uid, created.metadata.uid);
+        assert_eq!(
+            restored.metadata.resource_version,
+            created.metadata.resource_version
+        );
+
+        let mut update = restored.as_update();
+        update.spec = MySpec { value: 2 };
+        let updated = client.replace_item(update).await.expect("replace");
+        let version = |k8_obj: &K8Obj<MySpec>| {
+            k8_obj
+                .metadata
+                .resource_version
+                .parse::<u64>()
+                .expect("version")
+        };
+        assert!(version(&updated) > version(&removed));
+
+        // history before restart is not known
+        let mut stream = client.watch_stream_since::<MySpec, _>(
+            NameSpace::All,
+            Some(created.metadata.resource_version.clone()),
+        );
+        let mut events = stream.next().await.expect("error").expect("events");
+        let Ok(K8Watch::ERROR(status)) = events.remove(0) else {
+            panic!("expected error");
+        };
+        assert_eq!(status.code, Some(410));
+
+        std::fs::remove_dir_all(&directory).expect("cleanup");
+    }
+
+    #[fluvio_future::test]
+    async fn test_memory_files_garbage_collection() {
+        use k8_types::core::service::ServiceSpec;
+        use k8_types::InputK8Obj;
+
+        let directory =
+            std::env::temp_dir().join(format!("memory-client-{}", rand::random::<u32>()));
+
+        let client = MemoryClient::with_directory(&directory).expect("open");
+        let owner = client
+            .create_item(K8Obj::new("owner", ServiceSpec::default()).as_input())
+            .await
+            .expect("owner");
+        let metadata = owner
+            .metadata
+            .make_child_input_metadata::<ServiceSpec>("child".to_owned());
+        let child = client
+            .create_item(InputK8Obj::new(MySpec::default(), metadata))
+            .await
+            .expect("child");
+        let unrelated = client
+            .create_item(K8Obj::new("unrelated", MySpec::default()).as_input())
+            .await
+            .expect("unrelated");
+        drop(client);
+
+        // owner is not in store after restart, since its kind is not used
+        let client = MemoryClient::with_directory(&directory).expect("open");
+        client
+            .delete_item::<MySpec, _>(&unrelated.metadata)
+            .await
+            .expect("delete");
+        assert!(client
+            .retrieve_item::<MySpec, _>(&child.metadata)
+            .await
+            .expect("retrieve")
+            .is_some());
+
+        // dependent is collected once owner is deleted
+        client
+            .delete_item::<ServiceSpec, _>(&owner.metadata)
+            .await
+            .expect("delete");
+        assert!(client
+            .retrieve_item::<MySpec, _>(&child.metadata)
+            .await
+            .expect("retrieve")
+            .is_none());
+
+        std::fs::remove_dir_all(&directory).expect("cleanup");
+    }
 }
@@ -0,0 +1,218 @@
+//!
+//! # Files
+//!
+//! Keeps objects of memory client in directory so they survive restart.
+//! Each object is stored as YAML at `<kind>/<namespace>/<name>.yaml`,
+//! cluster scoped objects at `<kind>/<name>.yaml`.
+//! Namespace and name are percent encoded, except for characters valid in DNS names,
+//! so that object can't be stored outside of its directory.
+//! Files are replaced atomically by writing temporary file and renaming it over old file.
+//! Version of last deletion is kept in `.version`, since it is not found in any object file.
+//! Files are written in blocking tasks, store stays locked until file is written so that
+//! files are changed in same order as objects.
+//!
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde_yaml::Value;
+
+use fluvio_future::task::spawn_blocking;
+
+const EXTENSION: &str = "yaml";
+const VERSION_FILE: &str = ".version";
+
+/// files of single kind
+#[derive(Debug)]
+pub(super) struct Files {
+    root: PathBuf,
+    version_file: PathBuf,
+}
+
+impl Files {
+    pub(super) fn new(directory: &Path, kind: &str) -> Self {
+        Self {
+            root: directory.join(kind),
+            version_file: directory.join(VERSION_FILE),
+        }
+    }
+
+    /// file of object with store key `<namespace>/<name>`
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        let (namespace, name) = key
+            .split_once('/')
+            .ok_or_else(|| anyhow!("invalid key: {}", key))?;
+        if name.is_empty() {
+            return Err(anyhow!("object without name: {}", key));
+        }
+        let mut path = self.root.clone();
+        if !namespace.is_empty() {
+            path.push(encode(namespace));
+        }
+        path.push(format!("{}.{EXTENSION}", encode(name)));
+        Ok(path)
+    }
+
+    pub(super) async fn write(&self, key: &str, value: &Value) -> Result<()> {
+        let path = self.path(key)?;
+        let contents = serde_yaml::to_string(value)?;
+        spawn_blocking(move || write_atomic(&path, &contents)).await
+    }
+
+    /// remove object deleted at version
+    pub(super) async fn remove(&self, key: &str, version: u64) -> Result<()> {
+        let path = self.path(key)?;
+        let version_file = self.version_file.clone();
+        spawn_blocking(move || {
+            match fs::remove_file(&path) {
+                Err(err) if err.kind() != ErrorKind::NotFound => {
+                    return Err(anyhow!("removing {}: {}", path.display(), err));
+                }
+                _ => {}
+            }
+            write_atomic(&version_file, &version.to_string())
+        })
+        .await
+    }
+
+    /// objects keyed by store key
+    fn load(&self) -> Result<BTreeMap<String, Value>> {
+        let mut objects = BTreeMap::new();
+        for entry in fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                let namespace = decode(&file_name(&path))?;
+                for entry in fs::read_dir(&path)? {
+                    let path = entry?.path();
+                    if let Some(name) = object_name(&path) {
+                        objects.insert(format!("{namespace}/{}", decode(&name)?), read(&path)?);
+                    }
+                }
+            } else if let Some(name) = object_name(&path) {
+                objects.insert(format!("/{}", decode(&name)?), read(&path)?);
+            }
+        }
+        Ok(objects)
+    }
+}
+
+/// objects of all kinds in directory, keyed by kind and store key
+pub(super) fn load_all(root: &Path) -> Result<HashMap<String, BTreeMap<String, Value>>> {
+    fs::create_dir_all(root)?;
+    let mut kinds = HashMap::new();
+    for entry in fs::read_dir(root)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            let kind = file_name(&path);
+            let objects = Files::new(root, &kind).load()?;
+            kinds.insert(kind, objects);
+        }
+    }
+    Ok(kinds)
+}
+
+/// version of last deletion
+pub(super) fn load_version(root: &Path) -> Result<u64> {
+    match fs::read_to_string(root.join(VERSION_FILE)) {
+        Ok(version) => Ok(version.trim().parse()?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+
+    let temp = parent.join(format!(".{}.tmp", file_name(path).trim_start_matches('.')));
+    fs::write(&temp, contents).with_context(|| format!("writing {}", temp.display()))?;
+    fs::rename(&temp, path).with_context(|| format!("replacing {}", path.display()))?;
+    Ok(())
+}
+
+/// percent encode all characters except those valid in DNS names, leading `.` is encoded
+/// so that name can't refer to parent directory or be mistaken for temporary file
+fn encode(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for (index, byte) in segment.bytes().enumerate() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => encoded.push(byte as char),
+            b'.' if index > 0 => encoded.push('.'),
+            byte => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn decode(file_name: &str) -> Result<String> {
+    let mut decoded = Vec::with_capacity(file_name.len());
+    let mut bytes = file_name.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [bytes.next(), bytes.next()]
+                .into_iter()
+                .collect::<Option<Vec<u8>>>()
+                .and_then(|hex| u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok())
+                .ok_or_else(|| anyhow!("invalid file name: {}", file_name))?;
+            decoded.push(hex);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    Ok(String::from_utf8(decoded)?)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// name of object stored in file, None for other files such as left over temporary files
+fn object_name(path: &Path) -> Option<String> {
+    let name = file_name(path);
+    if name.starts_with('.') {
+        return None;
+    }
+    name.strip_suffix(&format!(".{EXTENSION}"))
+        .map(|name| name.to_owned())
+}
+
+fn read(path: &Path) -> Result<Value> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    serde_yaml::from_str(&contents).with_context(|| format!("parsing {}", path.display()))
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::path::Path;
+
+    use super::{decode, encode, Files};
+
+    #[test]
+    fn test_file_names() {
+        for name in [
+            "web-1",
+            "app.example.com",
+            "system:admin",
+            "../etc",
+            "a/b",
+            ".hidden",
+            "ü",
+        ] {
+            assert_eq!(decode(&encode(name)).expect("decode"), name);
+        }
+        assert_eq!(encode("app.example.com"), "app.example.com");
+        assert_eq!(encode(".."), "%2E.");
+
+        let files = Files::new(Path::new("/data"), "Pod");
+        let path = files.path("../../etc/passwd").expect("path");
+        assert_eq!(path, Path::new("/data/Pod/%2E./%2E.%2Fetc%2Fpasswd.yaml"));
+        assert!(files.path("default/").is_err());
+    }
+}
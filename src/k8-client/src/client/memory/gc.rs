@@ -0,0 +1,227 @@
+//!
+//! # Garbage Collection
+//!
+//! Removes objects once their finalizers are done and deletes dependents whose owners are gone,
+//! similar to garbage collector of Kubernetes.
+//! Dependent which still has an owner that is not being deleted in foreground is kept,
+//! only its references to other owners are removed.
+//! Collection runs to completion after each change which may need it, so unlike Kubernetes there is no delay.
+//! Objects loaded from directory, whose store is not created yet, are only seen as owners and dependents,
+//! they are not collected until their store is created.
+//!
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, PoisonError};
+
+use anyhow::Result;
+use tracing::debug;
+
+use k8_types::ObjectMeta;
+
+use super::{MemoryClient, SpecStore};
+
+/// finalizer which keeps owner until its dependents are deleted
+pub(super) const FOREGROUND_DELETION: &str = "foregroundDeletion";
+
+enum Action {
+    /// finalizers are done
+    Remove,
+    /// dependents of owner deleted in foreground are gone
+    ReleaseForeground,
+    /// owners are gone or being deleted in foreground
+    Delete { foreground: bool },
+    /// other owners keep dependent, references to owners which are gone
+    /// or being deleted in foreground are removed
+    RemoveOwners(Vec<String>),
+}
+
+struct Entry {
+    store: Arc<SpecStore>,
+    key: String,
+    metadata: ObjectMeta,
+}
+
+impl MemoryClient {
+    async fn entries(&self) -> Result<Vec<Entry>> {
+        let stores: Vec<Arc<SpecStore>> = self
+            .data
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .cloned()
+            .collect();
+
+        let mut entries = vec![];
+        for store in stores {
+            for (key, metadata) in store.metadata().await? {
+                entries.push(Entry {
+                    store: store.clone(),
+                    key,
+                    metadata,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    /// metadata of objects loaded from directory which are not in store yet
+    fn loaded_metadata(&self) -> Result<Vec<ObjectMeta>> {
+        let loaded = self.loaded.lock().unwrap_or_else(PoisonError::into_inner);
+        loaded
+            .values()
+            .flat_map(|objects| objects.values())
+            .map(|value| {
+                let metadata = value.get("metadata").cloned().unwrap_or_default();
+                Ok(serde_yaml::from_value(metadata)?)
+            })
+            .collect()
+    }
+
+    /// remove references to owner from its dependents
+    pub(super) async fn orphan_dependents(&self, owner: &str) -> Result<()> {
+        for entry in self.entries().await? {
+            let owned = entry
+                .metadata
+                .owner_references
+                .iter()
+                .any(|reference| reference.uid == owner);
+            if owned {
+                debug!(key = %entry.key, owner, "orphaning");
+                entry
+                    .store
+                    .update_metadata(&entry.key, &entry.metadata.uid, |metadata| {
+                        metadata
+                            .owner_references
+                            .retain(|reference| reference.uid != owner)
+                    })
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// collect until there is nothing left to collect
+    pub(super) async fn collect_garbage(&self) -> Result<()> {
+        loop {
+            let entries = self.entries().await?;
+            let loaded = self.loaded_metadata()?;
+            let actions = plan(&entries, &loaded);
+            if actions.is_empty() {
+                return Ok(());
+            }
+
+            for (entry, action) in actions {
+                let uid = entry.metadata.uid.as_str();
+                match action {
+                    Action::Remove => {
+                        debug!(key = %entry.key, uid, "removing finalized");
+                        entry.store.remove(&entry.key, uid).await?;
+                    }
+                    Action::ReleaseForeground => {
+                        debug!(key = %entry.key, uid, "dependents deleted");
+                        entry
+                            .store
+                            .update_metadata(&entry.key, uid, |metadata| {
+                                metadata
+                                    .finalizers
+                                    .retain(|finalizer| finalizer != FOREGROUND_DELETION)
+                            })
+                            .await?;
+                    }
+                    Action::Delete { foreground } => {
+                        debug!(key = %entry.key, uid, foreground, "deleting dependent");
+                        entry.store.delete(&entry.key, uid, foreground).await?;
+                    }
+                    Action::RemoveOwners(owners) => {
+                        debug!(key = %entry.key, uid, ?owners, "removing owners");
+                        entry
+                            .store
+                            .update_metadata(&entry.key, uid, |metadata| {
+                                metadata
+                                    .owner_references
+                                    .retain(|reference| !owners.contains(&reference.uid))
+                            })
+                            .await?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// actions for entries in stores. loaded objects are not changed, but they keep their owners
+/// and dependents
+fn plan<'a>(entries: &'a [Entry], loaded: &[ObjectMeta]) -> Vec<(&'a Entry, Action)> {
+    let all = || {
+        entries
+            .iter()
+            .map(|entry| &entry.metadata)
+            .chain(loaded.iter())
+    };
+    let objects: HashMap<&str, &ObjectMeta> = all()
+        .map(|metadata| (metadata.uid.as_str(), metadata))
+        .collect();
+    let owners: HashSet<&str> = all()
+        .flat_map(|metadata| metadata.owner_references.iter())
+        .map(|reference| reference.uid.as_str())
+        .collect();
+
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let metadata = &entry.metadata;
+            let has_dependents = owners.contains(metadata.uid.as_str());
+
+            let action = if metadata.deletion_timestamp.is_some() {
+                if metadata.finalizers.is_empty() {
+                    Action::Remove
+                } else if is_foreground(metadata) && !has_dependents {
+                    Action::ReleaseForeground
+                } else {
+                    return None;
+                }
+            } else {
+                if metadata.owner_references.is_empty() {
+                    return None;
+                }
+                let live_owners: Vec<&ObjectMeta> = metadata
+                    .owner_references
+                    .iter()
+                    .filter_map(|reference| objects.get(reference.uid.as_str()).copied())
+                    .collect();
+                let has_solid_owner = live_owners.iter().any(|owner| !is_foreground(owner));
+                if live_owners.is_empty() {
+                    Action::Delete { foreground: false }
+                } else if has_solid_owner {
+                    let removed: Vec<String> = metadata
+                        .owner_references
+                        .iter()
+                        .filter(|reference| {
+                            objects
+                                .get(reference.uid.as_str())
+                                .is_none_or(|owner| is_foreground(owner))
+                        })
+                        .map(|reference| reference.uid.clone())
+                        .collect();
+                    if removed.is_empty() {
+                        return None;
+                    }
+                    Action::RemoveOwners(removed)
+                } else {
+                    Action::Delete {
+                        foreground: has_dependents,
+                    }
+                }
+            };
+            Some((entry, action))
+        })
+        .collect()
+}
+
+/// owner is being deleted in foreground
+fn is_foreground(metadata: &ObjectMeta) -> bool {
+    metadata.deletion_timestamp.is_some()
+        && metadata
+            .finalizers
+            .iter()
+            .any(|finalizer| finalizer == FOREGROUND_DELETION)
+}
@@ -0,0 +1,349 @@
+//!
+//! # Patch
+//!
+//! Applies patches to objects stored by memory client.
+//! Strategic merge is approximated since schema of object is not known:
+//! list of objects are merged by well known merge keys such as `name`, other lists are replaced.
+//!
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+
+use k8_metadata_client::PatchMergeType;
+
+/// keys used to merge list of objects, first key present in all patch items is used
+const MERGE_KEYS: &[&str] = &[
+    "name",
+    "mountPath",
+    "devicePath",
+    "containerPort",
+    "port",
+    "ip",
+    "type",
+];
+
+const DIRECTIVE: &str = "$patch";
+
+pub(super) fn apply_patch(
+    target: &mut Value,
+    patch: &Value,
+    merge_type: &PatchMergeType,
+) -> Result<()> {
+    match merge_type {
+        PatchMergeType::Json => json_patch(target, patch),
+        PatchMergeType::JsonMerge => {
+            merge_patch(target, patch);
+            Ok(())
+        }
+        PatchMergeType::StrategicMerge | PatchMergeType::Apply(_) => {
+            strategic_merge_patch(target, patch);
+            Ok(())
+        }
+    }
+}
+
+/// RFC 7386 JSON merge patch
+pub(super) fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch_map) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    if let Value::Object(target_map) = target {
+        for (key, value) in patch_map {
+            if value.is_null() {
+                target_map.remove(key);
+            } else {
+                merge_patch(target_map.entry(key).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// RFC 6902 JSON patch
+pub(super) fn json_patch(target: &mut Value, patch: &Value) -> Result<()> {
+    let Value::Array(operations) = patch else {
+        return Err(anyhow!("json patch must be array of operations"));
+    };
+
+    for operation in operations {
+        let op = operation
+            .get("op")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("missing op in json patch: {}", operation))?;
+        let path = operation
+            .get("path")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("missing path in json patch: {}", operation))?;
+        let value = || {
+            operation
+                .get("value")
+                .cloned()
+                .ok_or_else(|| anyhow!("missing value in json patch: {}", operation))
+        };
+        let from = || {
+            operation
+                .get("from")
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("missing from in json patch: {}", operation))
+        };
+
+        match op {
+            "add" => add(target, path, value()?)?,
+            "remove" => {
+                remove(target, path)?;
+            }
+            "replace" => {
+                let current = target
+                    .pointer_mut(path)
+                    .ok_or_else(|| anyhow!("path not found: {}", path))?;
+                *current = value()?;
+            }
+            "move" => {
+                let moved = remove(target, from()?)?;
+                add(target, path, moved)?;
+            }
+            "copy" => {
+                let from = from()?;
+                let copied = target
+                    .pointer(from)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("path not found: {}", from))?;
+                add(target, path, copied)?;
+            }
+            "test" => {
+                let expected = value()?;
+                if target.pointer(path) != Some(&expected) {
+                    return Err(anyhow!("test failed for path: {}", path));
+                }
+            }
+            _ => return Err(anyhow!("unknown json patch op: {}", op)),
+        }
+    }
+    Ok(())
+}
+
+/// split pointer into parent pointer and unescaped last token
+fn split_pointer(path: &str) -> Result<(&str, String)> {
+    let (parent, last) = path
+        .rsplit_once('/')
+        .ok_or_else(|| anyhow!("invalid path: {}", path))?;
+    Ok((parent, last.replace("~1", "/").replace("~0", "~")))
+}
+
+fn add(target: &mut Value, path: &str, value: Value) -> Result<()> {
+    if path.is_empty() {
+        *target = value;
+        return Ok(());
+    }
+    let (parent, key) = split_pointer(path)?;
+    match target.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.insert(key, value);
+        }
+        Some(Value::Array(items)) => {
+            let index = if key == "-" {
+                items.len()
+            } else {
+                key.parse::<usize>()?
+            };
+            if index > items.len() {
+                return Err(anyhow!("index out of bounds: {}", path));
+            }
+            items.insert(index, value);
+        }
+        _ => return Err(anyhow!("path not found: {}", path)),
+    }
+    Ok(())
+}
+
+fn remove(target: &mut Value, path: &str) -> Result<Value> {
+    let (parent, key) = split_pointer(path)?;
+    let removed = match target.pointer_mut(parent) {
+        Some(Value::Object(map)) => map.remove(&key),
+        Some(Value::Array(items)) => {
+            let index = key.parse::<usize>()?;
+            (index < items.len()).then(|| items.remove(index))
+        }
+        _ => None,
+    };
+    removed.ok_or_else(|| anyhow!("path not found: {}", path))
+}
+
+/// approximation of strategic merge patch.
+/// maps are merged as in JSON merge patch, list of objects are merged by merge key.
+/// `$patch: replace` on map and `$patch: delete` on list item are supported
+pub(super) fn strategic_merge_patch(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(patch_map) => {
+            if patch_map.get(DIRECTIVE).and_then(Value::as_str) == Some("replace") {
+                *target = without_directives(patch);
+                return;
+            }
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            if let Value::Object(target_map) = target {
+                for (key, value) in patch_map {
+                    if key.starts_with('$') {
+                        continue;
+                    }
+                    if value.is_null() {
+                        target_map.remove(key);
+                    } else {
+                        strategic_merge_patch(target_map.entry(key).or_insert(Value::Null), value);
+                    }
+                }
+            }
+        }
+        Value::Array(patch_items) => match (target, merge_key(patch_items)) {
+            (Value::Array(target_items), Some(merge_key)) => {
+                merge_list(target_items, patch_items, merge_key)
+            }
+            (target, _) => *target = without_directives(patch),
+        },
+        _ => *target = patch.clone(),
+    }
+}
+
+fn merge_list(target_items: &mut Vec<Value>, patch_items: &[Value], merge_key: &str) {
+    for patch_item in patch_items {
+        let key = patch_item.get(merge_key);
+        let position = target_items
+            .iter()
+            .position(|item| item.get(merge_key) == key);
+        let delete = patch_item.get(DIRECTIVE).and_then(Value::as_str) == Some("delete");
+        match (position, delete) {
+            (Some(position), true) => {
+                target_items.remove(position);
+            }
+            (Some(position), false) => {
+                strategic_merge_patch(&mut target_items[position], patch_item)
+            }
+            (None, true) => {}
+            (None, false) => target_items.push(without_directives(patch_item)),
+        }
+    }
+}
+
+fn merge_key(items: &[Value]) -> Option<&'static str> {
+    if items.is_empty() {
+        return None;
+    }
+    MERGE_KEYS
+        .iter()
+        .find(|key| items.iter().all(|item| item.get(**key).is_some()))
+        .copied()
+}
+
+/// remove directives and null values
+fn without_directives(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(key, value)| !key.starts_with('$') && !value.is_null())
+                .map(|(key, value)| (key.clone(), without_directives(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(without_directives).collect()),
+        _ => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use serde_json::json;
+
+    use super::{json_patch, merge_patch, strategic_merge_patch};
+
+    #[test]
+    fn test_merge_patch() {
+        let mut target = json!({"a": "b", "c": {"d": "e", "f": "g"}, "list": [1, 2]});
+        merge_patch(
+            &mut target,
+            &json!({"a": "z", "c": {"f": null}, "list": [3], "new": {"x": null, "y": 1}}),
+        );
+        assert_eq!(
+            target,
+            json!({"a": "z", "c": {"d": "e"}, "list": [3], "new": {"y": 1}})
+        );
+    }
+
+    #[test]
+    fn test_json_patch() {
+        let mut target =
+            json!({"spec": {"replicas": 1, "args": ["a", "c"]}, "metadata": {"labels": {}}});
+        json_patch(
+            &mut target,
+            &json!([
+                {"op": "test", "path": "/spec/replicas", "value": 1},
+                {"op": "replace", "path": "/spec/replicas", "value": 3},
+                {"op": "add", "path": "/spec/args/1", "value": "b"},
+                {"op": "add", "path": "/spec/args/-", "value": "d"},
+                {"op": "add", "path": "/metadata/labels/app~1name", "value": "test"},
+                {"op": "copy", "from": "/spec/replicas", "path": "/spec/min"},
+                {"op": "move", "from": "/spec/min", "path": "/spec/max"},
+                {"op": "remove", "path": "/spec/args/0"}
+            ]),
+        )
+        .expect("patch");
+        assert_eq!(
+            target,
+            json!({"spec": {"replicas": 3, "max": 3, "args": ["b", "c", "d"]}, "metadata": {"labels": {"app/name": "test"}}})
+        );
+
+        let failed = json_patch(
+            &mut target,
+            &json!([{"op": "test", "path": "/spec/replicas", "value": 1}]),
+        );
+        assert!(failed.is_err());
+        assert!(json_patch(
+            &mut target,
+            &json!([{"op": "remove", "path": "/spec/none"}])
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_strategic_merge_patch() {
+        let mut target = json!({
+            "containers": [
+                {"name": "app", "image": "app:1", "ports": [{"containerPort": 80}]},
+                {"name": "sidecar", "image": "sidecar:1"}
+            ],
+            "args": ["a"],
+            "labels": {"app": "test", "tier": "web"}
+        });
+        strategic_merge_patch(
+            &mut target,
+            &json!({
+                "containers": [
+                    {"name": "app", "image": "app:2"},
+                    {"name": "sidecar", "$patch": "delete"},
+                    {"name": "init", "image": "init:1"}
+                ],
+                "args": ["b"],
+                "labels": {"tier": null}
+            }),
+        );
+        assert_eq!(
+            target,
+            json!({
+                "containers": [
+                    {"name": "app", "image": "app:2", "ports": [{"containerPort": 80}]},
+                    {"name": "init", "image": "init:1"}
+                ],
+                "args": ["b"],
+                "labels": {"app": "test"}
+            })
+        );
+
+        strategic_merge_patch(
+            &mut target,
+            &json!({"labels": {"$patch": "replace", "only": "this"}}),
+        );
+        assert_eq!(target["labels"], json!({"only": "this"}));
+    }
+}
@@ -0,0 +1,94 @@
+//!
+//! # Selector
+//!
+//! Evaluates label and field selectors of list requests against stored objects.
+//! Fields are evaluated against object serialized to JSON.
+//!
+use anyhow::Result;
+
+use k8_types::{FieldSelector, K8Obj, Spec};
+
+/// label and field selectors of list request
+#[derive(Debug, Default)]
+pub(super) struct Selector {
+    labels: k8_types::Selector,
+    fields: FieldSelector,
+}
+
+impl Selector {
+    pub(super) fn parse(
+        label_selector: Option<&str>,
+        field_selector: Option<&str>,
+    ) -> Result<Self> {
+        Ok(Self {
+            labels: label_selector.unwrap_or_default().parse()?,
+            fields: field_selector.unwrap_or_default().parse()?,
+        })
+    }
+
+    pub(super) fn matches<S: Spec>(&self, item: &K8Obj<S>) -> bool {
+        self.labels.matches(&item.metadata.labels) && self.fields.matches(item)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use k8_types::core::pod::PodSpec;
+    use k8_types::K8Obj;
+
+    use super::Selector;
+
+    fn pod(name: &str, labels: &[(&str, &str)]) -> K8Obj<PodSpec> {
+        let mut pod = K8Obj::new(name, PodSpec::default());
+        pod.metadata.namespace = "test".to_owned();
+        for (key, value) in labels {
+            pod.metadata
+                .labels
+                .insert(key.to_string(), value.to_string());
+        }
+        pod
+    }
+
+    #[test]
+    fn test_label_selector() {
+        let web = pod("web", &[("app", "web"), ("tier", "frontend")]);
+        let db = pod("db", &[("app", "db")]);
+
+        let selector = Selector::parse(Some("app=web"), None).expect("parse");
+        assert!(selector.matches(&web));
+        assert!(!selector.matches(&db));
+
+        let selector = Selector::parse(Some("app in (web, db),!tier"), None).expect("parse");
+        assert!(!selector.matches(&web));
+        assert!(selector.matches(&db));
+
+        let selector = Selector::parse(Some("app notin (db), tier"), None).expect("parse");
+        assert!(selector.matches(&web));
+        assert!(!selector.matches(&db));
+
+        let selector = Selector::parse(Some("app!=web"), None).expect("parse");
+        assert!(!selector.matches(&web));
+        assert!(selector.matches(&db));
+    }
+
+    #[test]
+    fn test_field_selector() {
+        let mut web = pod("web", &[]);
+
+        let selector = Selector::parse(None, Some("metadata.name==web,metadata.namespace=test"))
+            .expect("parse");
+        assert!(selector.matches(&web));
+
+        let selector = Selector::parse(None, Some("metadata.name!=web")).expect("parse");
+        assert!(!selector.matches(&web));
+
+        let selector =
+            Selector::parse(None, Some("status.phase!=Running,spec.nodeName=")).expect("parse");
+        assert!(selector.matches(&web));
+        web.spec.node_name = Some("node1".to_owned());
+        assert!(!selector.matches(&web));
+
+        assert!(Selector::parse(None, Some("status.phase in (Running)")).is_err());
+    }
+}
@@ -1,13 +1,36 @@
 mod client_impl;
+pub mod discovery;
+mod dynamic;
 mod log_stream;
 #[cfg(feature = "memory_client")]
 pub mod memory;
+#[cfg(feature = "fake_server")]
+pub mod fake_server;
+#[cfg(feature = "cassette")]
+pub mod cassette;
+#[cfg(feature = "ws")]
+mod exec;
+#[cfg(feature = "ws")]
+mod port_forward;
+#[cfg(feature = "ws")]
+mod ws;
 
 mod list_stream;
+mod rate_limit;
+mod resilient_watch;
+mod retry;
 mod wstream;
 
 pub use client_impl::K8Client;
+pub use dynamic::DynamicStreamResult;
+#[cfg(feature = "ws")]
+pub use exec::{AttachedProcess, ExecReader, ExecStatus, ExecWriter};
+#[cfg(feature = "ws")]
+pub use port_forward::{PortForward, PortForwardListener, PortStream};
 pub use log_stream::LogStream;
+pub use resilient_watch::WatchEvent;
+pub use rate_limit::{RateLimiter, DEFAULT_BURST, DEFAULT_QPS, DISCOVERY_BURST, DISCOVERY_QPS};
+pub use retry::RetryPolicy;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "openssl_tls")] {
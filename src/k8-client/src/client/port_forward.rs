@@ -0,0 +1,297 @@
+//!
+//! # Port Forward
+//!
+//! Forwarding ports of pod over WebSocket. Each port has data and error channel,
+//! first message on each channel starts with port number.
+//! Connection carries single stream for each port, so [PortForwardListener] opens
+//! new connection for each accepted local connection.
+//!
+use std::io;
+use std::net::{Shutdown, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use anyhow::{anyhow, Result};
+use async_channel::{bounded, unbounded, Receiver, Sender, WeakSender};
+use bytes::Bytes;
+use futures_util::future::{join, select, Either};
+use futures_util::io::{copy, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use futures_util::stream::{BoxStream, IntoAsyncRead, SplitStream, StreamExt, TryStreamExt};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, trace};
+
+use fluvio_future::net::{TcpListener, TcpStream};
+use fluvio_future::task::spawn;
+use k8_types::core::pod::PodSpec;
+
+use crate::uri::item_uri;
+
+use super::ws::{next_binary, write_messages, ChannelWriter, WsStream, CHANNEL_CAPACITY, V4_CHANNEL};
+use super::K8Client;
+
+/// each port takes two channels
+const MAX_PORTS: usize = 127;
+
+/// stream to port of pod. since protocol can't close single port, closing stream only
+/// stops writing to port, connection is closed when all streams are closed or dropped.
+/// data received for port is buffered up to limit, other ports wait while stream is not read
+pub struct PortStream {
+    reader: IntoAsyncRead<BoxStream<'static, io::Result<Bytes>>>,
+    writer: ChannelWriter,
+}
+
+impl AsyncRead for PortStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.reader).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for PortStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.writer.poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.writer.poll_pending(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.writer.poll_close(cx)
+    }
+}
+
+/// forwarded ports of pod, connection is closed when all streams are dropped or closed
+pub struct PortForward {
+    ports: Vec<u16>,
+    streams: Vec<Option<PortStream>>,
+}
+
+impl PortForward {
+    fn new(stream: WsStream, ports: &[u16]) -> Self {
+        let (sink, stream) = stream.split();
+        let (outgoing, outgoing_receiver) = bounded(CHANNEL_CAPACITY);
+        let (senders, receivers): (Vec<_>, Vec<_>) =
+            ports.iter().map(|_| bounded(CHANNEL_CAPACITY)).unzip();
+
+        spawn(write_messages(sink, outgoing_receiver));
+        spawn(read_messages(stream, senders, outgoing.downgrade()));
+
+        let streams = receivers
+            .into_iter()
+            .enumerate()
+            .map(|(index, receiver): (usize, Receiver<io::Result<Bytes>>)| {
+                Some(PortStream {
+                    reader: receiver.boxed().into_async_read(),
+                    writer: ChannelWriter::new(outgoing.clone(), (index * 2) as u8),
+                })
+            })
+            .collect();
+        Self {
+            ports: ports.to_vec(),
+            streams,
+        }
+    }
+
+    pub fn ports(&self) -> &[u16] {
+        &self.ports
+    }
+
+    /// take stream of port, None if port is not forwarded or stream is already taken.
+    /// streams which are not taken are dropped with this
+    pub fn take_stream(&mut self, port: u16) -> Option<PortStream> {
+        let index = self.ports.iter().position(|forwarded| *forwarded == port)?;
+        self.streams[index].take()
+    }
+}
+
+/// demultiplex messages from server until connection is closed.
+/// message on error channel is returned as error by reader of port.
+/// outgoing is weak so connection is closed when all streams are closed or dropped
+async fn read_messages(
+    mut stream: SplitStream<WsStream>,
+    ports: Vec<Sender<io::Result<Bytes>>>,
+    outgoing: WeakSender<Message>,
+) {
+    let mut connected = vec![false; ports.len() * 2];
+    while let Some(data) = next_binary(&mut stream).await {
+        let Some((&channel, mut payload)) = data.split_first() else {
+            continue;
+        };
+        let Some(port) = ports.get(channel as usize / 2) else {
+            trace!(channel, "ignoring message on unknown channel");
+            continue;
+        };
+        if !connected[channel as usize] {
+            connected[channel as usize] = true;
+            payload = payload.get(2..).unwrap_or_default();
+        }
+        if payload.is_empty() {
+            continue;
+        }
+        let data = if channel % 2 == 0 {
+            Ok(Bytes::copy_from_slice(payload))
+        } else {
+            Err(io::Error::other(
+                String::from_utf8_lossy(payload).into_owned(),
+            ))
+        };
+        // data is dropped if stream is dropped
+        let _ = port.send(data).await;
+    }
+    if let Some(outgoing) = outgoing.upgrade() {
+        outgoing.close();
+    }
+}
+
+/// forwards connections accepted on local address to port of pod, like `kubectl port-forward`.
+/// listener stops accepting connections when dropped
+pub struct PortForwardListener {
+    address: SocketAddr,
+    _shutdown: Sender<()>,
+}
+
+impl PortForwardListener {
+    /// local address, port is picked by OS if it was 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+/// target of [PortForwardListener]
+#[derive(Clone)]
+struct Target {
+    client: Arc<K8Client>,
+    namespace: String,
+    pod_name: String,
+    port: u16,
+}
+
+impl Target {
+    /// copy data in both directions until both sides are closed.
+    /// local side closing its write half doesn't close port, since protocol can't close
+    /// single direction, so response is still copied until pod closes connection
+    async fn forward(self, local: TcpStream) {
+        let mut forward = match self
+            .client
+            .port_forward(&self.namespace, &self.pod_name, &[self.port])
+            .await
+        {
+            Ok(forward) => forward,
+            Err(err) => {
+                error!(%err, port = self.port, "port forward failed");
+                return;
+            }
+        };
+        let Some(remote) = forward.take_stream(self.port) else {
+            return;
+        };
+        let (mut remote_reader, mut remote_writer) = remote.split();
+        let (mut local_reader, mut local_writer) = (local.clone(), local);
+
+        let sent = async {
+            let result = copy(&mut local_reader, &mut remote_writer).await;
+            if result.is_err() {
+                let _ = remote_writer.close().await;
+            }
+            result
+        };
+        let received = async {
+            let result = copy(&mut remote_reader, &mut local_writer).await;
+            let _ = local_writer.shutdown(Shutdown::Write);
+            result
+        };
+        let (sent, received) = join(sent, received).await;
+        for result in [sent, received] {
+            match result {
+                Ok(_) => trace!(port = self.port, "port forward connection closed"),
+                Err(err) => debug!(%err, port = self.port, "port forward connection failed"),
+            }
+        }
+    }
+
+    /// accept connections until listener is dropped
+    async fn accept(self, listener: TcpListener, closed: Receiver<()>) {
+        loop {
+            match select(Box::pin(listener.accept()), Box::pin(closed.recv())).await {
+                Either::Left((Ok((stream, peer)), _)) => {
+                    debug!(%peer, port = self.port, "forwarding connection");
+                    spawn(self.clone().forward(stream));
+                }
+                Either::Left((Err(err), _)) => {
+                    error!(%err, "port forward accept failed");
+                    return;
+                }
+                Either::Right(_) => {
+                    debug!(port = self.port, "port forward stopped");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl K8Client {
+    /// forward ports of pod, data of each port is read and written thru [PortStream]
+    pub async fn port_forward(
+        &self,
+        namespace: &str,
+        pod_name: &str,
+        ports: &[u16],
+    ) -> Result<PortForward> {
+        if ports.is_empty() || ports.len() > MAX_PORTS {
+            return Err(anyhow!(
+                "number of ports must be between 1 and {}",
+                MAX_PORTS
+            ));
+        }
+        let query = ports
+            .iter()
+            .map(|port| format!("ports={port}"))
+            .collect::<Vec<_>>()
+            .join("&");
+        let uri = item_uri::<PodSpec>(
+            self.hostname(),
+            pod_name,
+            namespace,
+            Some("/portforward"),
+            Some(&query),
+        )?;
+        let (stream, _) = self.connect_ws(uri, &[V4_CHANNEL]).await?;
+        Ok(PortForward::new(stream, ports))
+    }
+
+    /// listen on local address and forward each accepted connection to port of pod
+    pub async fn port_forward_listener(
+        self: Arc<Self>,
+        namespace: &str,
+        pod_name: &str,
+        port: u16,
+        address: SocketAddr,
+    ) -> Result<PortForwardListener> {
+        let listener = TcpListener::bind(address).await?;
+        let address = listener.local_addr()?;
+        let (shutdown, closed) = unbounded();
+        let target = Target {
+            client: self,
+            namespace: namespace.to_owned(),
+            pod_name: pod_name.to_owned(),
+            port,
+        };
+        debug!(%address, port, "port forward listening");
+        spawn(target.accept(listener, closed));
+
+        Ok(PortForwardListener {
+            address,
+            _shutdown: shutdown,
+        })
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::{Mutex as StdMutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use hyper::{Body, Response, StatusCode};
+use tracing::{debug, trace, warn};
+
+use fluvio_future::timer::sleep;
+
+/// default queries per second, same as client-go
+pub const DEFAULT_QPS: f64 = 5.0;
+/// default burst, same as client-go
+pub const DEFAULT_BURST: u32 = 10;
+/// default queries per second of discovery, same as kubectl
+pub const DISCOVERY_QPS: f64 = 50.0;
+/// default burst of discovery, same as kubectl
+pub const DISCOVERY_BURST: u32 = 300;
+
+const FLOW_SCHEMA_UID: &str = "x-kubernetes-pf-flowschema-uid";
+const PRIORITY_LEVEL_UID: &str = "x-kubernetes-pf-prioritylevel-uid";
+
+#[derive(Debug)]
+struct Bucket {
+    /// available tokens, negative if requests are waiting for tokens
+    tokens: f64,
+    refilled: Instant,
+}
+
+/// Token bucket limiting rate of requests sent to API server.
+/// Bucket holds up to burst tokens and is refilled at qps rate, each request takes one token.
+#[derive(Debug)]
+pub struct RateLimiter {
+    qps: f64,
+    burst: u32,
+    bucket: StdMutex<Bucket>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(DEFAULT_QPS, DEFAULT_BURST)
+    }
+}
+
+impl RateLimiter {
+    /// limiter of discovery requests, [DISCOVERY_QPS] with [DISCOVERY_BURST]
+    pub fn discovery() -> Self {
+        Self::new(DISCOVERY_QPS, DISCOVERY_BURST)
+    }
+
+    pub fn new(qps: f64, burst: u32) -> Self {
+        let burst = burst.max(1);
+        Self {
+            qps: qps.max(f64::MIN_POSITIVE),
+            burst,
+            bucket: StdMutex::new(Bucket {
+                tokens: burst as f64,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn qps(&self) -> f64 {
+        self.qps
+    }
+
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// take token, returns how long to wait before token is available
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+        let elapsed = now.saturating_duration_since(bucket.refilled);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.qps).min(self.burst as f64);
+        bucket.refilled = now;
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.qps)
+        }
+    }
+
+    /// wait until request can be sent
+    pub async fn acquire(&self) {
+        let delay = self.reserve(Instant::now());
+        if !delay.is_zero() {
+            debug!(
+                delay_ms = delay.as_millis() as u64,
+                "client side throttling"
+            );
+            sleep(delay).await;
+        }
+    }
+}
+
+/// trace API priority and fairness classification of response,
+/// so rejections by flow control can be told apart from other throttling
+pub(crate) fn trace_flow_control(response: &Response<Body>) {
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    let flow_schema = header(FLOW_SCHEMA_UID);
+    let priority_level = header(PRIORITY_LEVEL_UID);
+    if flow_schema.is_none() && priority_level.is_none() {
+        return;
+    }
+
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        warn!(
+            flow_schema_uid = flow_schema,
+            priority_level_uid = priority_level,
+            "request rejected by API priority and fairness"
+        );
+    } else {
+        trace!(
+            flow_schema_uid = flow_schema,
+            priority_level_uid = priority_level,
+            status = response.status().as_u16(),
+            "flow control"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::time::{Duration, Instant};
+
+    use super::RateLimiter;
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::new(10.0, 3);
+        let start = limiter.bucket.lock().expect("lock").refilled;
+
+        // burst is available immediately
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve(start), Duration::ZERO);
+        }
+        // then requests are spaced by 1/qps
+        assert_eq!(limiter.reserve(start), Duration::from_millis(100));
+        assert_eq!(limiter.reserve(start), Duration::from_millis(200));
+
+        // bucket refills, but not above burst
+        let later = start + Duration::from_secs(10);
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve(later), Duration::ZERO);
+        }
+        assert_eq!(limiter.reserve(later), Duration::from_millis(100));
+    }
+
+    #[fluvio_future::test]
+    async fn test_acquire_waits() {
+        let limiter = RateLimiter::new(50.0, 1);
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(35));
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use tracing::{debug, error, trace};
+
+use fluvio_future::retry::FibonacciBackoff;
+use fluvio_future::timer::sleep;
+use k8_types::options::ListOptions;
+use k8_types::{K8List, K8Watch, Spec};
+
+use crate::meta_client::{K8Error, K8ErrorExt, NameSpace, TokenStreamResult};
+use crate::uri::items_uri;
+
+use super::K8Client;
+
+const GONE: u16 = 410;
+const WATCH_TIMEOUT_SECONDS: u32 = 3600;
+
+/// event from resilient watch
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum WatchEvent<S>
+where
+    S: Spec,
+{
+    /// change received from watch
+    Event(K8Watch<S>),
+    /// watch expired and objects have been listed again.
+    /// list replaces any state built from previous events
+    Resync(K8List<S>),
+}
+
+struct ResilientWatch<'a, S>
+where
+    S: Spec,
+{
+    client: &'a K8Client,
+    namespace: NameSpace,
+    resource_version: Option<String>,
+    stream: Option<BoxStream<'a, TokenStreamResult<S>>>,
+    pending: VecDeque<Result<WatchEvent<S>>>,
+    received: bool,
+    backoff: FibonacciBackoff,
+}
+
+impl<'a, S> ResilientWatch<'a, S>
+where
+    S: Spec + 'static,
+{
+    fn new_backoff() -> FibonacciBackoff {
+        FibonacciBackoff::from_millis(100).max_delay(Duration::from_secs(30))
+    }
+
+    async fn next_event(&mut self) -> Result<WatchEvent<S>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return event;
+            }
+
+            let stream = match self.stream.as_mut() {
+                Some(stream) => stream,
+                None => {
+                    let opt = ListOptions {
+                        watch: Some(true),
+                        resource_version: self.resource_version.clone(),
+                        timeout_seconds: Some(WATCH_TIMEOUT_SECONDS),
+                        allow_watch_bookmarks: Some(true),
+                        ..Default::default()
+                    };
+                    let uri =
+                        items_uri::<S>(self.client.hostname(), self.namespace.clone(), Some(opt));
+                    debug!(
+                        resource_version = ?self.resource_version,
+                        "{}: watching", S::label()
+                    );
+                    self.received = false;
+                    self.stream.insert(self.client.try_stream(uri).boxed())
+                }
+            };
+
+            match stream.next().await {
+                Some(Ok(events)) => {
+                    for event in events {
+                        match event {
+                            Ok(K8Watch::BOOKMARK(obj)) => {
+                                trace!(resource_version = %obj.metadata.resource_version, "{}: bookmark", S::label());
+                                self.received = true;
+                                self.resource_version = Some(obj.metadata.resource_version);
+                            }
+                            Ok(K8Watch::ERROR(status)) if status.code == Some(GONE) => {
+                                self.resync().await;
+                                break;
+                            }
+                            Ok(K8Watch::ERROR(status)) => self
+                                .pending
+                                .push_back(Err(K8Error::from(status).into_anyhow())),
+                            Ok(event) => {
+                                self.received = true;
+                                if let K8Watch::ADDED(obj)
+                                | K8Watch::MODIFIED(obj)
+                                | K8Watch::DELETED(obj) = &event
+                                {
+                                    self.resource_version =
+                                        Some(obj.metadata.resource_version.clone());
+                                }
+                                self.pending.push_back(Ok(WatchEvent::Event(event)));
+                            }
+                            Err(err) if err.is_gone() => {
+                                self.resync().await;
+                                break;
+                            }
+                            Err(err) => self.pending.push_back(Err(err)),
+                        }
+                    }
+                }
+                Some(Err(err)) if err.is_gone() => self.resync().await,
+                Some(Err(err)) => {
+                    error!(%err, "{}: watch failed, reconnecting", S::label());
+                    self.stream = None;
+                    self.wait().await;
+                }
+                None => {
+                    trace!("{}: watch ended, reconnecting", S::label());
+                    self.stream = None;
+                    if self.received {
+                        self.backoff = Self::new_backoff();
+                    } else {
+                        self.wait().await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// list all objects and restart watch from list's version
+    async fn resync(&mut self) {
+        debug!("{}: watch expired, listing again", S::label());
+        self.stream = None;
+        self.pending.clear();
+        loop {
+            match self
+                .client
+                .retrieve_items_inner::<S, _>(self.namespace.clone(), None)
+                .await
+            {
+                Ok(list) => {
+                    self.resource_version = Some(list.metadata.resource_version.clone());
+                    self.pending.push_back(Ok(WatchEvent::Resync(list)));
+                    self.backoff = Self::new_backoff();
+                    return;
+                }
+                Err(err) => {
+                    error!(%err, "{}: list failed", S::label());
+                    self.wait().await;
+                }
+            }
+        }
+    }
+
+    async fn wait(&mut self) {
+        if let Some(delay) = self.backoff.next() {
+            sleep(delay).await;
+        }
+    }
+}
+
+impl K8Client {
+    /// Watch that doesn't end when server closes connection.
+    /// Watch is resumed from last resource version seen, with backoff if there is failure.
+    /// If resource version has expired, objects are listed again and [WatchEvent::Resync] is emitted.
+    /// Bookmarks are requested from server and used to advance resource version, they are not emitted.
+    /// Errors decoding individual events are returned without ending the stream.
+    pub fn watch_stream_resilient<S, N>(
+        &self,
+        namespace: N,
+        resource_version: Option<String>,
+    ) -> BoxStream<'_, Result<WatchEvent<S>>>
+    where
+        S: Spec + 'static,
+        N: Into<NameSpace>,
+    {
+        let watch = ResilientWatch {
+            client: self,
+            namespace: namespace.into(),
+            resource_version,
+            stream: None,
+            pending: VecDeque::new(),
+            received: false,
+            backoff: ResilientWatch::<S>::new_backoff(),
+        };
+
+        stream::unfold(watch, |mut watch| async move {
+            let event = watch.next_event().await;
+            Some((event, watch))
+        })
+        .boxed()
+    }
+}
@@ -0,0 +1,359 @@
+use std::error::Error as StdError;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures_util::future::Future;
+use http::header::RETRY_AFTER;
+use http::{HeaderMap, StatusCode};
+use hyper::body::to_bytes;
+use hyper::{Body, Request, Response};
+use rand::Rng;
+use tracing::{debug_span, warn, Instrument};
+
+use fluvio_future::timer::sleep;
+
+use crate::meta_client::{K8Error, K8ErrorExt};
+
+/// Retry of requests which failed with transient error.
+///
+/// Only idempotent verbs (GET, PUT, DELETE...) are retried, when API server is throttling (429),
+/// unavailable (502, 503, 504) or connection failed.
+/// Delay grows exponentially from initial backoff with random jitter, unless server sends `Retry-After`.
+/// Both are limited by max backoff.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// policy which never retries
+    pub fn disabled() -> Self {
+        Self::default().with_max_attempts(1)
+    }
+
+    /// attempts including first one
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// delay before first retry, it is doubled for each retry
+    pub fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// fraction of delay which is randomized, between 0.0 and 1.0
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// delay before retry following attempt
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+        let jitter = self.jitter * rand::thread_rng().gen::<f64>();
+        delay.mul_f64(1.0 - jitter)
+    }
+
+    /// delay before retry of response, `Retry-After` is limited by max backoff
+    fn response_backoff(&self, headers: &HeaderMap, attempt: u32) -> Duration {
+        match retry_after(headers) {
+            Some(delay) => delay.min(self.max_backoff),
+            None => self.backoff(attempt),
+        }
+    }
+
+    /// send request, repeating it while it fails with transient error
+    pub(crate) async fn send<F, Fut>(
+        &self,
+        request: Request<Body>,
+        send: F,
+    ) -> Result<Response<Body>>
+    where
+        F: Fn(Request<Body>) -> Fut,
+        Fut: Future<Output = Result<Response<Body>>>,
+    {
+        if self.max_attempts <= 1 || !request.method().is_idempotent() {
+            return send(request).await;
+        }
+
+        // body is sent again for each attempt
+        let (parts, body) = request.into_parts();
+        let body = to_bytes(body).await.map_err(K8Error::transport)?;
+
+        let mut attempt = 1;
+        loop {
+            let mut request = Request::new(Body::from(body.clone()));
+            *request.method_mut() = parts.method.clone();
+            *request.uri_mut() = parts.uri.clone();
+            *request.version_mut() = parts.version;
+            *request.headers_mut() = parts.headers.clone();
+
+            let span = debug_span!(
+                "k8_request",
+                method = %parts.method,
+                uri = %parts.uri,
+                attempt
+            );
+            let result = send(request).instrument(span.clone()).await;
+            if attempt >= self.max_attempts {
+                return result;
+            }
+
+            let delay = match &result {
+                Ok(response) if is_retryable_status(response.status()) => {
+                    let delay = self.response_backoff(response.headers(), attempt);
+                    span.in_scope(|| {
+                        warn!(
+                            status = response.status().as_u16(),
+                            delay_ms = delay.as_millis() as u64,
+                            "retrying request"
+                        )
+                    });
+                    delay
+                }
+                Err(err) if is_transient(err) => {
+                    let delay = self.backoff(attempt);
+                    span.in_scope(
+                        || warn!(%err, delay_ms = delay.as_millis() as u64, "retrying request"),
+                    );
+                    delay
+                }
+                _ => return result,
+            };
+
+            drop(result);
+            sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// delay requested by server in seconds, API server doesn't send dates
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// connection failed or was reset before response was received
+fn is_transient(err: &anyhow::Error) -> bool {
+    let Some(K8Error::Transport(err)) = err.k8_error() else {
+        return false;
+    };
+    if let Some(err) = err.downcast_ref::<hyper::Error>() {
+        if err.is_connect() || err.is_closed() || err.is_incomplete_message() || err.is_timeout() {
+            return true;
+        }
+    }
+
+    let mut source: Option<&(dyn StdError + 'static)> = Some(err.as_ref());
+    while let Some(err) = source {
+        if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+            if matches!(
+                io_err.kind(),
+                ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::ConnectionRefused
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::TimedOut
+                    | ErrorKind::UnexpectedEof
+            ) {
+                return true;
+            }
+        }
+        source = err.source();
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    use http::header::{HeaderValue, RETRY_AFTER};
+    use http::HeaderMap;
+    use hyper::body::to_bytes;
+    use hyper::{Body, Request, Response, StatusCode};
+
+    use crate::meta_client::K8Error;
+
+    use super::RetryPolicy;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::default()
+            .with_initial_backoff(Duration::from_millis(1))
+            .with_max_attempts(3)
+    }
+
+    fn response(status: StatusCode) -> Response<Body> {
+        Response::builder()
+            .status(status)
+            .header("Retry-After", "0")
+            .body(Body::empty())
+            .expect("response")
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(500))
+            .with_jitter(0.0);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+
+        let policy = policy.with_jitter(0.5);
+        for _ in 0..100 {
+            let delay = policy.backoff(2);
+            assert!(delay > Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let policy = RetryPolicy::default()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_secs(5))
+            .with_jitter(0.0);
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            policy.response_backoff(&headers, 1),
+            Duration::from_millis(100)
+        );
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("2"));
+        assert_eq!(policy.response_backoff(&headers, 1), Duration::from_secs(2));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3600"));
+        assert_eq!(policy.response_backoff(&headers, 1), Duration::from_secs(5));
+    }
+
+    #[fluvio_future::test]
+    async fn test_retry_until_success() {
+        let attempts = AtomicU32::new(0);
+        let request = Request::put("http://localhost/api/v1/pods/web")
+            .body(Body::from("{}"))
+            .expect("request");
+
+        let response = policy()
+            .send(request, |request| {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    assert_eq!(to_bytes(request.into_body()).await?, "{}");
+                    Ok(response(match attempt {
+                        0 => StatusCode::TOO_MANY_REQUESTS,
+                        1 => StatusCode::SERVICE_UNAVAILABLE,
+                        _ => StatusCode::OK,
+                    }))
+                }
+            })
+            .await
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[fluvio_future::test]
+    async fn test_no_retry() {
+        let attempts = AtomicU32::new(0);
+        let send = |_| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            async { Ok(response(StatusCode::TOO_MANY_REQUESTS)) }
+        };
+
+        // attempts are exhausted
+        let request = Request::get("http://localhost/api").body(Body::empty());
+        let result = policy().send(request.expect("request"), send).await;
+        assert_eq!(result.expect("response").status(), 429);
+        assert_eq!(attempts.swap(0, Ordering::SeqCst), 3);
+
+        // post is not idempotent
+        let request = Request::post("http://localhost/api").body(Body::empty());
+        policy()
+            .send(request.expect("request"), send)
+            .await
+            .expect("response");
+        assert_eq!(attempts.swap(0, Ordering::SeqCst), 1);
+
+        // conflict is not transient
+        let request = Request::get("http://localhost/api").body(Body::empty());
+        policy()
+            .send(request.expect("request"), |_| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async { Ok(response(StatusCode::CONFLICT)) }
+            })
+            .await
+            .expect("response");
+        assert_eq!(attempts.swap(0, Ordering::SeqCst), 1);
+
+        // connection errors are retried
+        let request = Request::get("http://localhost/api").body(Body::empty());
+        let err = policy()
+            .send(request.expect("request"), |_| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async {
+                    Err(K8Error::transport(std::io::Error::from(
+                        std::io::ErrorKind::ConnectionReset,
+                    ))
+                    .into())
+                }
+            })
+            .await
+            .expect_err("reset");
+        assert!(matches!(
+            err.downcast_ref::<K8Error>(),
+            Some(K8Error::Transport(_))
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+}
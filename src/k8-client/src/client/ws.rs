@@ -0,0 +1,206 @@
+//!
+//! # WebSocket
+//!
+//! Upgrade of request to streaming subresources, such as `exec` or `portforward`,
+//! to WebSocket connection. Messages are multiplexed by channel number in first byte.
+//!
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use anyhow::{anyhow, Result};
+use async_channel::{Receiver, Sender, TrySendError};
+use futures_util::stream::{SplitSink, SplitStream, StreamExt};
+use futures_util::SinkExt;
+use hyper::header::{
+    CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_PROTOCOL,
+    SEC_WEBSOCKET_VERSION, UPGRADE,
+};
+use hyper::upgrade::Upgraded;
+use hyper::{Body, Request, StatusCode, Uri};
+use tokio_tungstenite::tungstenite::handshake::client::generate_key;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+use tracing::{debug, trace};
+
+use crate::meta_client::K8Error;
+
+use super::client_impl::error_response;
+use super::K8Client;
+
+/// subprotocol with channel close message
+pub(crate) const V5_CHANNEL: &str = "v5.channel.k8s.io";
+pub(crate) const V4_CHANNEL: &str = "v4.channel.k8s.io";
+
+/// channel of stdin for exec, first data channel for port forward
+pub(crate) const STDIN: u8 = 0;
+pub(crate) const STDOUT: u8 = 1;
+pub(crate) const STDERR: u8 = 2;
+/// status of exec
+pub(crate) const ERROR: u8 = 3;
+/// terminal size of exec
+pub(crate) const RESIZE: u8 = 4;
+/// in `v5.channel.k8s.io`, message on this channel closes channel in next byte
+pub(crate) const CLOSE: u8 = 255;
+
+/// messages buffered in each direction, sender waits when buffer is full
+pub(crate) const CHANNEL_CAPACITY: usize = 16;
+
+pub(crate) type WsStream = WebSocketStream<Upgraded>;
+
+impl K8Client {
+    /// connect to subresource, returns stream and subprotocol picked by server
+    pub(super) async fn connect_ws(
+        &self,
+        uri: Uri,
+        protocols: &[&str],
+    ) -> Result<(WsStream, String)> {
+        debug!("websocket connecting: {}", uri);
+        let key = generate_key();
+        let mut request = Request::get(uri)
+            .header(CONNECTION, "Upgrade")
+            .header(UPGRADE, "websocket")
+            .header(SEC_WEBSOCKET_VERSION, "13")
+            .header(SEC_WEBSOCKET_KEY, &key)
+            .header(SEC_WEBSOCKET_PROTOCOL, protocols.join(", "))
+            .body(Body::empty())?;
+        self.finish_request(&mut request)?;
+
+        let response = self.send(request).await?;
+        if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+            return Err(error_response(response).await);
+        }
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_owned())
+        };
+        if header(SEC_WEBSOCKET_ACCEPT) != Some(derive_accept_key(key.as_bytes())) {
+            return Err(anyhow!("invalid websocket accept key"));
+        }
+        let protocol = header(SEC_WEBSOCKET_PROTOCOL)
+            .filter(|protocol| protocols.contains(&protocol.as_str()))
+            .ok_or_else(|| anyhow!("server doesn't support protocols: {:?}", protocols))?;
+        debug!(%protocol, "websocket connected");
+
+        let upgraded = hyper::upgrade::on(response)
+            .await
+            .map_err(K8Error::transport)?;
+        let stream = WebSocketStream::from_raw_socket(upgraded, Role::Client, None).await;
+        Ok((stream, protocol))
+    }
+}
+
+/// next binary message, None when connection is closed
+pub(super) async fn next_binary(stream: &mut SplitStream<WsStream>) -> Option<Vec<u8>> {
+    while let Some(message) = stream.next().await {
+        match message {
+            Ok(Message::Binary(data)) => return Some(data),
+            Ok(Message::Close(frame)) => {
+                debug!(?frame, "websocket closed");
+                return None;
+            }
+            Ok(message) => trace!(?message, "ignoring message"),
+            Err(err) => {
+                debug!(%err, "websocket receive failed");
+                return None;
+            }
+        }
+    }
+    None
+}
+
+/// send messages until all senders are dropped or channel is closed
+pub(super) async fn write_messages(
+    mut sink: SplitSink<WsStream, Message>,
+    outgoing: Receiver<Message>,
+) {
+    while let Ok(message) = outgoing.recv().await {
+        if let Err(err) = sink.send(message).await {
+            debug!(%err, "websocket send failed");
+            return;
+        }
+    }
+    let _ = sink.close().await;
+}
+
+/// writer of data on channel. write is pending while previous message doesn't fit
+/// into outgoing buffer
+pub(super) struct ChannelWriter {
+    /// None when writer is closed
+    outgoing: Option<Sender<Message>>,
+    channel: u8,
+    /// message waiting for space in outgoing buffer
+    pending: Option<Pin<Box<dyn Future<Output = io::Result<()>> + Send>>>,
+}
+
+impl ChannelWriter {
+    pub(super) fn new(outgoing: Sender<Message>, channel: u8) -> Self {
+        Self {
+            outgoing: Some(outgoing),
+            channel,
+            pending: None,
+        }
+    }
+
+    /// queue message, it is sent when there is space in outgoing buffer
+    pub(super) fn start_send(&mut self, message: Message) -> io::Result<()> {
+        let Some(outgoing) = &self.outgoing else {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        };
+        match outgoing.try_send(message) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(message)) => {
+                let outgoing = outgoing.clone();
+                self.pending = Some(Box::pin(async move {
+                    outgoing
+                        .send(message)
+                        .await
+                        .map_err(|_| io::ErrorKind::BrokenPipe.into())
+                }));
+                Ok(())
+            }
+            Err(TrySendError::Closed(_)) => Err(io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    /// wait until queued message is in outgoing buffer
+    pub(super) fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let Some(pending) = self.pending.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+        let result = ready!(pending.as_mut().poll(cx));
+        self.pending = None;
+        Poll::Ready(result)
+    }
+
+    pub(super) fn poll_write(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.poll_pending(cx))?;
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let mut data = Vec::with_capacity(buf.len() + 1);
+        data.push(self.channel);
+        data.extend_from_slice(buf);
+        self.start_send(Message::Binary(data))?;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    /// stop writing after queued message is sent. connection is closed when all
+    /// writers are closed or dropped
+    pub(super) fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_pending(cx))?;
+        self.outgoing = None;
+        Poll::Ready(Ok(()))
+    }
+}
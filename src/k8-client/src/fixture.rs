@@ -1,3 +1,3 @@
-/// common test fixtures
+// common test fixtures
 
 pub const TEST_NS: &str = "test";
@@ -0,0 +1,300 @@
+//!
+//! # Leader Election
+//!
+//! Elects single leader among candidates sharing a `coordination.k8s.io` Lease.
+//! Lease is acquired and renewed using resource version of last observed lease, so only one
+//! candidate can win when multiple candidates update at same time.
+//!
+//! Expiration of lease is measured using local clock from the time lease was last observed to change,
+//! so clock skew between candidates doesn't matter.
+//!
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::Result;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use tracing::{debug, error, trace};
+
+use fluvio_future::timer::sleep;
+use k8_metadata_client::{MetadataClient, SharedClient};
+use k8_types::coordination::lease::LeaseSpec;
+use k8_types::{InputK8Obj, InputObjectMeta, K8Obj};
+
+const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(15);
+const DEFAULT_RENEW_DEADLINE: Duration = Duration::from_secs(10);
+const DEFAULT_RETRY_PERIOD: Duration = Duration::from_secs(2);
+
+/// change in leadership
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LeaderEvent {
+    /// this candidate became leader
+    Acquired,
+    /// this candidate is no longer leader
+    Lost,
+    /// lease is held by other candidate
+    NewLeader(String),
+}
+
+struct ObservedLease {
+    lease: K8Obj<LeaseSpec>,
+    time: Instant,
+}
+
+/// Candidate for leadership of lease
+///
+/// ```ignore
+/// let mut events = LeaderElector::new(client, "default", "my-operator", pod_name)
+///     .with_lease_duration(Duration::from_secs(30))
+///     .into_stream();
+/// while let Some(event) = events.next().await {
+///     match event {
+///         LeaderEvent::Acquired => start(),
+///         LeaderEvent::Lost => stop(),
+///         LeaderEvent::NewLeader(leader) => info!(leader, "following"),
+///     }
+/// }
+/// ```
+pub struct LeaderElector<C> {
+    client: SharedClient<C>,
+    metadata: InputObjectMeta,
+    identity: String,
+    lease_duration: Duration,
+    renew_deadline: Duration,
+    retry_period: Duration,
+    observed: Option<ObservedLease>,
+    last_renew: Option<Instant>,
+    leader: bool,
+}
+
+impl<C> LeaderElector<C>
+where
+    C: MetadataClient + 'static,
+{
+    /// identity must be unique among candidates, ex: pod name
+    pub fn new(
+        client: SharedClient<C>,
+        namespace: impl Into<String>,
+        name: impl Into<String>,
+        identity: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            metadata: InputObjectMeta::named(name.into(), namespace.into()),
+            identity: identity.into(),
+            lease_duration: DEFAULT_LEASE_DURATION,
+            renew_deadline: DEFAULT_RENEW_DEADLINE,
+            retry_period: DEFAULT_RETRY_PERIOD,
+            observed: None,
+            last_renew: None,
+            leader: false,
+        }
+    }
+
+    /// how long other candidates wait before taking over lease which is not renewed
+    pub fn with_lease_duration(mut self, lease_duration: Duration) -> Self {
+        self.lease_duration = lease_duration;
+        self
+    }
+
+    /// how long leader keeps retrying to renew before giving up leadership.
+    /// should be less than lease duration
+    pub fn with_renew_deadline(mut self, renew_deadline: Duration) -> Self {
+        self.renew_deadline = renew_deadline;
+        self
+    }
+
+    /// interval between attempts to acquire or renew
+    pub fn with_retry_period(mut self, retry_period: Duration) -> Self {
+        self.retry_period = retry_period;
+        self
+    }
+
+    pub fn identity(&self) -> &str {
+        &self.identity
+    }
+
+    /// true if last attempt to acquire or renew was successful
+    pub fn is_leader(&self) -> bool {
+        self.leader
+    }
+
+    /// current holder of lease as last observed
+    pub fn leader(&self) -> Option<&str> {
+        self.observed
+            .as_ref()
+            .and_then(|observed| observed.lease.spec.holder_identity.as_deref())
+            .filter(|holder| !holder.is_empty())
+    }
+
+    /// try to acquire lease or renew it if already held.
+    /// returns true if this candidate holds the lease
+    pub async fn try_acquire_or_renew(&mut self) -> Result<bool> {
+        let now = SystemTime::now();
+
+        let Some(lease) = self
+            .client
+            .retrieve_item::<LeaseSpec, _>(&self.metadata)
+            .await?
+        else {
+            debug!(identity = %self.identity, lease = %self.metadata, "creating lease");
+            let input = InputK8Obj::new(self.new_record(None, now), self.metadata.clone());
+            let lease = self.client.create_item(input).await?;
+            self.observe(lease);
+            return Ok(self.renewed());
+        };
+
+        self.observe(lease.clone());
+
+        if !self.can_acquire(&lease.spec) {
+            trace!(identity = %self.identity, holder = ?lease.spec.holder_identity, "lease is held");
+            self.leader = false;
+            return Ok(false);
+        }
+
+        let mut update = lease.as_update();
+        update.spec = self.new_record(Some(&lease.spec), now);
+        let lease = self.client.replace_item(update).await?;
+        self.observe(lease);
+        Ok(self.renewed())
+    }
+
+    /// give up lease so other candidate can acquire it without waiting for expiration
+    pub async fn release(&mut self) -> Result<()> {
+        if !self.leader {
+            return Ok(());
+        }
+        self.leader = false;
+
+        let Some(lease) = self
+            .client
+            .retrieve_item::<LeaseSpec, _>(&self.metadata)
+            .await?
+        else {
+            return Ok(());
+        };
+        if lease.spec.holder_identity.as_deref() != Some(self.identity.as_str()) {
+            return Ok(());
+        }
+
+        debug!(identity = %self.identity, lease = %self.metadata, "releasing lease");
+        let mut update = lease.as_update();
+        update.spec = LeaseSpec {
+            holder_identity: None,
+            lease_duration_seconds: Some(1),
+            renew_time: Some(micro_time(SystemTime::now())),
+            ..lease.spec
+        };
+        let lease = self.client.replace_item(update).await?;
+        self.observe(lease);
+        Ok(())
+    }
+
+    /// keep acquiring or renewing lease until stream is dropped.
+    /// lease is not released when stream is dropped
+    pub fn into_stream(self) -> BoxStream<'static, LeaderEvent> {
+        let state = (self, VecDeque::new(), None::<Instant>);
+        stream::unfold(
+            state,
+            |(mut elector, mut events, mut next_try)| async move {
+                loop {
+                    if let Some(event) = events.pop_front() {
+                        return Some((event, (elector, events, next_try)));
+                    }
+
+                    if let Some(next_try) = next_try {
+                        sleep(next_try.saturating_duration_since(Instant::now())).await;
+                    }
+                    next_try = Some(Instant::now() + elector.retry_period);
+                    elector.step(&mut events).await;
+                }
+            },
+        )
+        .boxed()
+    }
+
+    /// single attempt, queue events resulting from change in leadership
+    async fn step(&mut self, events: &mut VecDeque<LeaderEvent>) {
+        let was_leader = self.leader;
+        let old_holder = self.leader().map(|holder| holder.to_owned());
+
+        if let Err(err) = self.try_acquire_or_renew().await {
+            error!(identity = %self.identity, %err, "failed to acquire or renew lease");
+            let expired = self
+                .last_renew
+                .is_none_or(|renew| renew.elapsed() >= self.renew_deadline);
+            if was_leader && expired {
+                self.leader = false;
+            }
+        }
+
+        if !was_leader && self.leader {
+            events.push_back(LeaderEvent::Acquired);
+        }
+        if was_leader && !self.leader {
+            events.push_back(LeaderEvent::Lost);
+        }
+        if let Some(holder) = self.leader() {
+            if holder != self.identity && Some(holder) != old_holder.as_deref() {
+                events.push_back(LeaderEvent::NewLeader(holder.to_owned()));
+            }
+        }
+    }
+
+    fn renewed(&mut self) -> bool {
+        self.leader = true;
+        self.last_renew = Some(Instant::now());
+        true
+    }
+
+    /// remember when lease was last seen changing
+    fn observe(&mut self, lease: K8Obj<LeaseSpec>) {
+        let changed = self.observed.as_ref().is_none_or(|observed| {
+            observed.lease.metadata.resource_version != lease.metadata.resource_version
+        });
+        if changed {
+            self.observed = Some(ObservedLease {
+                lease,
+                time: Instant::now(),
+            });
+        }
+    }
+
+    fn can_acquire(&self, spec: &LeaseSpec) -> bool {
+        match spec.holder_identity.as_deref() {
+            None | Some("") => true,
+            Some(holder) if holder == self.identity => true,
+            Some(_) => self
+                .observed
+                .as_ref()
+                .is_none_or(|observed| observed.time.elapsed() >= self.lease_duration),
+        }
+    }
+
+    fn new_record(&self, old: Option<&LeaseSpec>, now: SystemTime) -> LeaseSpec {
+        let now = micro_time(now);
+        let lease_duration_seconds = Some(self.lease_duration.as_secs().max(1) as i32);
+        match old {
+            Some(old) if old.holder_identity.as_deref() == Some(self.identity.as_str()) => {
+                LeaseSpec {
+                    lease_duration_seconds,
+                    renew_time: Some(now),
+                    ..old.clone()
+                }
+            }
+            _ => LeaseSpec {
+                holder_identity: Some(self.identity.clone()),
+                lease_duration_seconds,
+                acquire_time: Some(now.clone()),
+                renew_time: Some(now),
+                lease_transitions: Some(
+                    old.map_or(0, |old| old.lease_transitions.unwrap_or_default() + 1),
+                ),
+            },
+        }
+    }
+}
+
+/// format time as MicroTime
+fn micro_time(time: SystemTime) -> String {
+    humantime::format_rfc3339_micros(time).to_string()
+}
@@ -4,6 +4,9 @@ mod uri;
 mod client;
 pub use self::client::*;
 
+mod leader_election;
+pub use leader_election::{LeaderElector, LeaderEvent};
+
 pub use k8_config::K8Config;
 
 #[cfg(feature = "k8")]
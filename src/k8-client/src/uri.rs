@@ -1,6 +1,6 @@
 use anyhow::Result;
 
-use k8_types::{Crd, Spec};
+use k8_types::{ApiResource, Crd, Spec};
 use k8_types::options::ListOptions;
 
 use crate::http::Uri;
@@ -55,15 +55,71 @@ where
 ///
 /// generate prefix for given crd
 /// if crd group is core then /api is used otherwise /apis + group
-
 pub fn prefix_uri<N>(crd: &Crd, host: &str, ns: N, options: Option<ListOptions>) -> String
 where
     N: Into<NameSpace>,
 {
-    let namespace = ns.into();
-    let version = crd.version;
-    let plural = crd.names.plural;
-    let group = crd.group;
+    group_prefix_uri(
+        crd.group,
+        crd.version,
+        crd.names.plural,
+        host,
+        ns.into(),
+        options,
+    )
+}
+
+/// prefix for resource known at runtime, namespace is ignored for cluster scoped resource
+pub fn resource_prefix_uri<N>(
+    resource: &ApiResource,
+    host: &str,
+    ns: N,
+    options: Option<ListOptions>,
+) -> String
+where
+    N: Into<NameSpace>,
+{
+    let ns = if resource.namespaced {
+        ns.into()
+    } else {
+        NameSpace::All
+    };
+    let group = if resource.is_core() {
+        "core"
+    } else {
+        &resource.group
+    };
+    group_prefix_uri(
+        group,
+        &resource.version,
+        &resource.plural,
+        host,
+        ns,
+        options,
+    )
+}
+
+/// uri of object of resource known at runtime
+pub fn resource_item_uri(
+    resource: &ApiResource,
+    host: &str,
+    name: &str,
+    namespace: &str,
+    query: Option<&str>,
+) -> Result<Uri> {
+    let prefix = resource_prefix_uri(resource, host, namespace, None);
+    let query = query.map(|q| format!("?{}", q)).unwrap_or_default();
+    Ok(format!("{prefix}/{name}{query}").parse()?)
+}
+
+fn group_prefix_uri(
+    group: &str,
+    version: &str,
+    plural: &str,
+    host: &str,
+    namespace: NameSpace,
+    options: Option<ListOptions>,
+) -> String {
     let api_prefix = match group {
         "core" => "api".to_owned(),
         _ => format!("apis/{}", group),
@@ -99,7 +155,7 @@ mod test {
     use k8_types::core::pod::PodSpec;
     use k8_types::{Crd, CrdNames, DEFAULT_NS};
 
-    use super::{prefix_uri, item_uri};
+    use super::{prefix_uri, item_uri, resource_item_uri, resource_prefix_uri};
     use super::ListOptions;
 
     const G1: Crd = Crd {
@@ -150,6 +206,35 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_resource_uri() {
+        use k8_types::ApiResource;
+
+        let namespaces = ApiResource {
+            group: String::new(),
+            version: "v1".to_owned(),
+            kind: "Namespace".to_owned(),
+            plural: "namespaces".to_owned(),
+            namespaced: false,
+        };
+        assert_eq!(
+            resource_prefix_uri(&namespaces, "https://localhost", DEFAULT_NS, None),
+            "https://localhost/api/v1/namespaces"
+        );
+
+        let items = ApiResource::from_crd(&G1, true);
+        assert_eq!(
+            resource_prefix_uri(&items, "https://localhost", DEFAULT_NS, None),
+            prefix_uri(&G1, "https://localhost", DEFAULT_NS, None)
+        );
+        assert_eq!(
+            resource_item_uri(&items, "https://localhost", "item1", DEFAULT_NS, None)
+                .expect("uri")
+                .to_string(),
+            "https://localhost/apis/test.com/v1/namespaces/default/items/item1"
+        );
+    }
+
     #[test]
     fn test_list_query() {
         let opt = ListOptions {
@@ -0,0 +1,71 @@
+#[cfg(feature = "k8")]
+mod integration_tests {
+
+    use std::collections::BTreeMap;
+
+    use anyhow::Result;
+    use rand::distributions::Alphanumeric;
+    use rand::{thread_rng, Rng};
+
+    use fluvio_future::test_async;
+    use k8_client::K8Client;
+    use k8_metadata_client::{ApplyConflict, ApplyOptions, MetadataClient};
+    use k8_types::core::config_map::{ConfigMapHeader, ConfigMapSpec};
+    use k8_types::{InputK8Obj, InputObjectMeta, Spec};
+
+    const NS: &str = "default";
+
+    fn create_client() -> K8Client {
+        K8Client::try_default().expect("cluster not initialized")
+    }
+
+    fn new_config_map(name: &str, value: &str) -> InputK8Obj<ConfigMapSpec> {
+        let mut data = BTreeMap::new();
+        data.insert("key".to_owned(), value.to_owned());
+
+        InputK8Obj {
+            api_version: ConfigMapSpec::api_version(),
+            kind: ConfigMapSpec::kind(),
+            metadata: InputObjectMeta::named(name, NS),
+            header: ConfigMapHeader { data },
+            ..Default::default()
+        }
+    }
+
+    #[test_async]
+    async fn test_server_side_apply_conflict() -> Result<()> {
+        let client = create_client();
+        let rname: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .map(char::from)
+            .take(5)
+            .collect();
+        let name = format!("apply{}", rname.to_lowercase());
+
+        let created = client
+            .server_side_apply(new_config_map(&name, "a"), ApplyOptions::new("alpha"))
+            .await?;
+        assert_eq!(created.header.data.get("key").unwrap(), "a");
+
+        let err = client
+            .server_side_apply(new_config_map(&name, "b"), ApplyOptions::new("beta"))
+            .await
+            .expect_err("conflict");
+        let conflict = err.downcast_ref::<ApplyConflict>().expect("apply conflict");
+        assert_eq!(conflict.fields(), vec![".data.key"]);
+        assert!(!conflict.conflicts[0].message.is_empty());
+
+        let forced = client
+            .server_side_apply(
+                new_config_map(&name, "b"),
+                ApplyOptions::new("beta").force(),
+            )
+            .await?;
+        assert_eq!(forced.header.data.get("key").unwrap(), "b");
+
+        client
+            .delete_item::<ConfigMapSpec, _>(&forced.metadata)
+            .await?;
+        Ok(())
+    }
+}
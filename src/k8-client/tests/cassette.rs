@@ -0,0 +1,135 @@
+#[cfg(feature = "cassette")]
+mod cassette_tests {
+
+    use std::path::PathBuf;
+
+    use anyhow::Result;
+    use futures_util::StreamExt;
+
+    use fluvio_future::test_async;
+    use k8_client::K8Client;
+    use k8_metadata_client::{K8ErrorExt, MetadataClient};
+    use k8_types::core::pod::PodSpec;
+    use k8_types::{InputObjectMeta, K8Watch};
+
+    const NS: &str = "default";
+
+    fn cassette(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("cassettes")
+            .join(name)
+    }
+
+    /// responses in cassette are written by hand in format of API server, not recorded from cluster
+    #[test_async]
+    async fn test_replay_handwritten_pods() -> Result<()> {
+        let client = K8Client::replay(cassette("handwritten_pods.json"))?;
+
+        let version = client.server_version().await?;
+        assert_eq!(version.git_version, "v1.29.2");
+
+        let pods = client.retrieve_items::<PodSpec, _>(NS).await?;
+        assert_eq!(pods.metadata.resource_version, "48230");
+        assert_eq!(pods.items.len(), 1);
+        let pod = &pods.items[0];
+        assert_eq!(pod.metadata.name, "web-0");
+        assert_eq!(pod.metadata.owner_references[0].kind, "StatefulSet");
+        assert_eq!(pod.spec.containers[0].image.as_deref(), Some("nginx:1.25"));
+        assert_eq!(pod.status.phase, "Running");
+        assert_eq!(pod.status.container_statuses[0].restart_count, 0);
+
+        let pod = client
+            .retrieve_item::<PodSpec, _>(&InputObjectMeta::named("web-0", NS))
+            .await?
+            .expect("pod");
+        assert_eq!(pod.metadata.uid, "5b8d2a4e-3c1f-4f7e-9a57-0c6f1c3b2d11");
+
+        let missing = client
+            .retrieve_item::<PodSpec, _>(&InputObjectMeta::named("missing", NS))
+            .await?;
+        assert!(missing.is_none());
+
+        let events: Vec<K8Watch<PodSpec>> = client
+            .watch_stream_since::<PodSpec, _>(NS, Some("48230".to_owned()))
+            .flat_map(|events| futures_util::stream::iter(events.expect("events")))
+            .map(|event| event.expect("event"))
+            .collect()
+            .await;
+        assert_eq!(events.len(), 2);
+        assert!(
+            matches!(&events[0], K8Watch::MODIFIED(pod) if pod.metadata.labels["tier"] == "frontend")
+        );
+        assert!(
+            matches!(&events[1], K8Watch::DELETED(pod) if pod.metadata.deletion_timestamp.is_some())
+        );
+
+        // each interaction is replayed once
+        let err = client
+            .retrieve_items::<PodSpec, _>(NS)
+            .await
+            .expect_err("replayed");
+        assert!(err.k8_error().is_none());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "fake_server")]
+    #[test_async]
+    async fn test_record_and_replay() -> Result<()> {
+        use std::collections::HashMap;
+
+        use k8_client::cassette::Cassette;
+        use k8_client::fake_server::FakeApiServer;
+        use k8_types::core::service::{ServicePort, ServiceSpec};
+        use k8_types::InputK8Obj;
+
+        let path =
+            std::env::temp_dir().join(format!("k8-client-cassette-{}.json", rand::random::<u32>()));
+
+        let server = FakeApiServer::builder()
+            .register::<ServiceSpec>()
+            .start()
+            .await?;
+        let client = server.k8_client()?.with_cassette(Cassette::record(&path));
+
+        let mut labels = HashMap::new();
+        labels.insert("app".to_owned(), "web".to_owned());
+        let input = InputK8Obj::new(
+            ServiceSpec {
+                ports: vec![ServicePort {
+                    port: 80,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            InputObjectMeta {
+                name: "web".to_owned(),
+                namespace: NS.to_owned(),
+                labels,
+                ..Default::default()
+            },
+        );
+        let created = client.create_item(input.clone()).await?;
+        let listed = client.retrieve_items::<ServiceSpec, _>(NS).await?;
+        let err = client.create_item(input.clone()).await.expect_err("exists");
+        assert!(err.is_already_exists());
+        drop(client);
+        drop(server);
+
+        let client = K8Client::replay(&path)?;
+        let replayed = client.create_item(input.clone()).await?;
+        assert_eq!(replayed.metadata.uid, created.metadata.uid);
+        let replayed_list = client.retrieve_items::<ServiceSpec, _>(NS).await?;
+        assert_eq!(
+            replayed_list.metadata.resource_version,
+            listed.metadata.resource_version
+        );
+        assert_eq!(replayed_list.items[0].metadata.labels["app"], "web");
+        let err = client.create_item(input).await.expect_err("exists");
+        assert!(err.is_already_exists());
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(feature = "memory_client")]
+mod memory_tests {
+
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use anyhow::anyhow;
+    use serde::{Deserialize, Serialize};
+
+    use fluvio_future::task::spawn;
+    use fluvio_future::test_async;
+    use k8_client::memory::MemoryClient;
+    use k8_metadata_client::{Action, Controller, MetadataClient};
+    use k8_types::{Crd, CrdNames, DefaultHeader, K8Obj, Spec, Status};
+
+    #[derive(Serialize, Deserialize, Default, Debug, Eq, PartialEq, Clone)]
+    struct MySpec {
+        value: i32,
+    }
+
+    #[derive(Serialize, Deserialize, Default, Debug, Eq, PartialEq, Clone)]
+    struct MySpecStatus {
+        value: i32,
+    }
+
+    impl Status for MySpecStatus {}
+
+    impl Spec for MySpec {
+        type Status = MySpecStatus;
+        type Header = DefaultHeader;
+
+        fn metadata() -> &'static Crd {
+            &Crd {
+                group: "test.fluvio",
+                version: "v1",
+                names: CrdNames {
+                    kind: "myspec",
+                    plural: "myspecs",
+                    singular: "myspec",
+                },
+            }
+        }
+    }
+
+    #[test_async]
+    async fn test_controller_reconcile() -> anyhow::Result<()> {
+        let client = MemoryClient::new_shared();
+        let attempts = Arc::new(AtomicU32::new(0));
+        let (sender, receiver) = async_channel::unbounded();
+
+        let controller = Controller::<MySpec, _>::new(client.clone(), "")
+            .with_concurrency(2)
+            .with_backoff(Duration::from_millis(10), Duration::from_millis(100));
+        let store = controller.store();
+
+        let reconcile_attempts = attempts.clone();
+        spawn(controller.run(move |obj: K8Obj<MySpec>| {
+            let attempts = reconcile_attempts.clone();
+            let sender = sender.clone();
+            async move {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                sender.send((obj.metadata.name.clone(), attempt)).await?;
+                match attempt {
+                    // first attempt fails and is retried with backoff
+                    0 => Err(anyhow!("transient failure")),
+                    // second attempt asks to be reconciled again
+                    1 => Ok(Action::Requeue(Duration::from_millis(10))),
+                    _ => Ok(Action::AwaitChange),
+                }
+            }
+        }));
+
+        store.wait_until_synced().await;
+        client
+            .create_item(K8Obj::new("one", MySpec { value: 1 }).as_input())
+            .await?;
+        assert_eq!(receiver.recv().await?, ("one".to_owned(), 0));
+        assert_eq!(receiver.recv().await?, ("one".to_owned(), 1));
+        assert_eq!(receiver.recv().await?, ("one".to_owned(), 2));
+
+        client
+            .create_item(K8Obj::new("two", MySpec { value: 2 }).as_input())
+            .await?;
+        assert_eq!(receiver.recv().await?, ("two".to_owned(), 3));
+
+        Ok(())
+    }
+}
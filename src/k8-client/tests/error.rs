@@ -13,10 +13,10 @@ mod integration_tests {
     use fluvio_future::timer::sleep;
     use k8_client::http::status::StatusCode;
     use k8_client::K8Client;
-    use k8_metadata_client::MetadataClient;
+    use k8_metadata_client::{K8ErrorExt, MetadataClient};
     use k8_types::core::service::{LoadBalancerType, ServicePort};
     use k8_types::core::service::{LoadBalancerIngress, ServiceSpec};
-    use k8_types::{InputK8Obj, InputObjectMeta, Spec, MetaStatus};
+    use k8_types::{InputK8Obj, InputObjectMeta, MetaStatus, Spec};
 
     const SPU_DEFAULT_NAME: &str = "spu";
     const DELAY: Duration = Duration::from_millis(100);
@@ -126,6 +126,7 @@ mod integration_tests {
         } else {
             panic!("expecting conflict error");
         }
+        assert!(err.is_conflict(), "expecting conflict error: {err}");
 
         // clean up
         let input_metadata: InputObjectMeta = updated_item.metadata.into();
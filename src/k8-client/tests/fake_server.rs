@@ -0,0 +1,653 @@
+#[cfg(feature = "fake_server")]
+mod fake_server_tests {
+
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use futures_util::io::AsyncReadExt;
+    use futures_util::StreamExt;
+
+    use fluvio_future::test_async;
+    use fluvio_future::timer::sleep;
+    use k8_client::fake_server::FakeApiServer;
+    use k8_client::K8Client;
+    use k8_metadata_client::{K8Error, K8ErrorExt, ListArg, MetadataClient, PatchMergeType};
+    use k8_types::core::pod::PodSpec;
+    use k8_types::core::service::{
+        LoadBalancerIngress, LoadBalancerStatus, ServicePort, ServiceSpec, ServiceStatus,
+    };
+    use k8_types::options::{DeleteOptions, PropogationPolicy, ResourceVersionMatch};
+    use k8_types::{DeleteStatus, FieldSelector, InputK8Obj, InputObjectMeta, K8Watch, Selector};
+
+    const NS: &str = "default";
+
+    async fn start() -> Result<(FakeApiServer, K8Client)> {
+        let server = FakeApiServer::builder()
+            .register::<PodSpec>()
+            .register::<ServiceSpec>()
+            .start()
+            .await?;
+        let client = server.k8_client()?;
+        Ok((server, client))
+    }
+
+    fn new_service(name: &str, app: &str) -> InputK8Obj<ServiceSpec> {
+        let mut labels = HashMap::new();
+        labels.insert("app".to_owned(), app.to_owned());
+        InputK8Obj::new(
+            ServiceSpec {
+                ports: vec![ServicePort {
+                    port: 9000,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            InputObjectMeta {
+                name: name.to_owned(),
+                namespace: NS.to_owned(),
+                labels,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn status_code(err: anyhow::Error) -> Option<u16> {
+        err.k8_error().and_then(K8Error::code)
+    }
+
+    #[test_async]
+    async fn test_fake_server_version() -> Result<()> {
+        let (_server, client) = start().await?;
+        let version = client.server_version().await?;
+        assert_eq!(version.major, "1");
+        Ok(())
+    }
+
+    #[test_async]
+    async fn test_fake_server_plain_http_rejected() -> Result<()> {
+        let (server, _client) = start().await?;
+        // only client of fake server may connect over http
+        let client = K8Client::new(server.k8_config())?;
+        let err = client.server_version().await.expect_err("http rejected");
+        assert!(format!("{err:?}").contains("http not supported"));
+        Ok(())
+    }
+
+    #[test_async]
+    async fn test_fake_server_crud() -> Result<()> {
+        let (server, client) = start().await?;
+
+        let created = client.create_item(new_service("web", "web")).await?;
+        assert!(!created.metadata.uid.is_empty());
+        let err = client
+            .create_item(new_service("web", "web"))
+            .await
+            .expect_err("already exists");
+        assert_eq!(status_code(err), Some(409));
+
+        let metadata = InputObjectMeta::named("web", NS);
+        let service = client
+            .retrieve_item::<ServiceSpec, _>(&metadata)
+            .await?
+            .expect("service");
+        assert_eq!(service.spec.ports[0].port, 9000);
+        assert!(client
+            .retrieve_item::<ServiceSpec, _>(&InputObjectMeta::named("none", NS))
+            .await?
+            .is_none());
+
+        // object is shared with memory client
+        assert!(server
+            .memory_client()
+            .retrieve_item::<ServiceSpec, _>(&metadata)
+            .await?
+            .is_some());
+
+        let mut update = service.as_update();
+        update.spec.ports[0].port = 9001;
+        let replaced = client.replace_item(update.clone()).await?;
+        assert_eq!(replaced.spec.ports[0].port, 9001);
+        let err = client
+            .replace_item(update)
+            .await
+            .expect_err("stale version");
+        assert_eq!(status_code(err), Some(409));
+
+        let status = ServiceStatus {
+            load_balancer: LoadBalancerStatus {
+                ingress: vec![LoadBalancerIngress {
+                    ip: Some("10.0.0.1".to_owned()),
+                    ..Default::default()
+                }],
+            },
+        };
+        let updated = client
+            .update_status(&replaced.as_status_update(status))
+            .await?;
+        assert_eq!(
+            updated.status.load_balancer.find_any_ip_or_host(),
+            Some("10.0.0.1")
+        );
+
+        let patched = client
+            .patch::<ServiceSpec, _>(
+                &metadata,
+                &serde_json::json!({"metadata": {"labels": {"tier": "frontend"}}}),
+                PatchMergeType::JsonMerge,
+            )
+            .await?;
+        assert_eq!(patched.metadata.labels["tier"], "frontend");
+
+        let status = client
+            .delete_item_with_option::<ServiceSpec, _>(
+                &metadata,
+                Some(DeleteOptions {
+                    propagation_policy: Some(PropogationPolicy::Background),
+                    ..Default::default()
+                }),
+            )
+            .await?;
+        assert!(matches!(status, DeleteStatus::Deleted(_)));
+        let err = client
+            .delete_item::<ServiceSpec, _>(&metadata)
+            .await
+            .expect_err("not found");
+        assert_eq!(status_code(err), Some(404));
+
+        Ok(())
+    }
+
+    #[test_async]
+    async fn test_fake_server_list() -> Result<()> {
+        let (_server, client) = start().await?;
+        for index in 0..5 {
+            let app = if index % 2 == 0 { "even" } else { "odd" };
+            client
+                .create_item(new_service(&format!("service{index}"), app))
+                .await?;
+        }
+
+        let all = client.retrieve_items::<ServiceSpec, _>(NS).await?;
+        assert_eq!(all.items.len(), 5);
+        let other = client.retrieve_items::<ServiceSpec, _>("other").await?;
+        assert!(other.items.is_empty());
+
+        let selected = client
+            .retrieve_items_with_option::<ServiceSpec, _>(
+                NS,
+                Some(ListArg {
+                    label_selector: Some("app=even".to_owned()),
+                    ..Default::default()
+                }),
+            )
+            .await?;
+        assert_eq!(selected.items.len(), 3);
+
+        let selected = client
+            .retrieve_items_with_option::<ServiceSpec, _>(
+                NS,
+                Some(
+                    ListArg::default()
+                        .with_label_selector(&Selector::new().not_in("app", ["even"]))
+                        .with_field_selector(&FieldSelector::new().name("service1")),
+                ),
+            )
+            .await?;
+        assert_eq!(selected.items.len(), 1);
+        assert_eq!(selected.items[0].metadata.name, "service1");
+
+        let first = client
+            .retrieve_items_with_option::<ServiceSpec, _>(
+                NS,
+                Some(
+                    ListArg::default()
+                        .with_limit(2)
+                        .with_resource_version("0", Some(ResourceVersionMatch::NotOlderThan)),
+                ),
+            )
+            .await?;
+        assert_eq!(first.items.len(), 2);
+        assert_eq!(first.metadata.remaining_item_count, Some(3));
+        let next = client
+            .retrieve_items_with_option::<ServiceSpec, _>(
+                NS,
+                Some(
+                    ListArg::default()
+                        .with_limit(2)
+                        .with_continue(first.metadata._continue.expect("continue")),
+                ),
+            )
+            .await?;
+        assert_eq!(next.items.len(), 2);
+        assert_eq!(next.metadata.remaining_item_count, Some(1));
+        assert_eq!(
+            next.metadata.resource_version,
+            first.metadata.resource_version
+        );
+
+        let chunks: Vec<usize> = Arc::new(client)
+            .retrieve_items_in_chunks::<ServiceSpec, _>(NS, 2, None)
+            .map(|list| list.items.len())
+            .collect()
+            .await;
+        assert_eq!(chunks, vec![2, 2, 1]);
+
+        Ok(())
+    }
+
+    #[test_async]
+    async fn test_fake_server_watch() -> Result<()> {
+        let (_server, client) = start().await?;
+        let client = Arc::new(client);
+
+        let list = client.retrieve_items::<ServiceSpec, _>(NS).await?;
+        let mut events =
+            client.watch_stream_since::<ServiceSpec, _>(NS, Some(list.metadata.resource_version));
+
+        let creator = client.clone();
+        fluvio_future::task::spawn(async move {
+            sleep(Duration::from_millis(50)).await;
+            creator
+                .create_item(new_service("watched", "web"))
+                .await
+                .expect("create");
+        });
+
+        let events = events.next().await.expect("event")?;
+        match events.into_iter().next().expect("event")? {
+            K8Watch::ADDED(service) => assert_eq!(service.metadata.name, "watched"),
+            event => panic!("unexpected event: {event:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[test_async]
+    async fn test_fake_server_resilient_watch() -> Result<()> {
+        use k8_client::WatchEvent;
+
+        let (server, client) = start().await?;
+        let list = client.retrieve_items::<ServiceSpec, _>(NS).await?;
+        let mut events = client.watch_stream_resilient::<ServiceSpec, _>(
+            NS,
+            Some(list.metadata.resource_version.clone()),
+        );
+
+        client.create_item(new_service("first", "web")).await?;
+        match events.next().await.expect("event")? {
+            WatchEvent::Event(K8Watch::ADDED(service)) => {
+                assert_eq!(service.metadata.name, "first")
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+
+        // watch is resumed from last version seen, so only change made while disconnected is sent
+        server.close_watches();
+        client.create_item(new_service("second", "web")).await?;
+        match events.next().await.expect("event")? {
+            WatchEvent::Event(K8Watch::ADDED(service)) => {
+                assert_eq!(service.metadata.name, "second")
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+
+        // enough changes to compact history past version of first list
+        let memory_client = server.memory_client();
+        for index in 0..1000 {
+            memory_client
+                .create_item(new_service(&format!("service{index}"), "web"))
+                .await?;
+        }
+        let mut events = client
+            .watch_stream_resilient::<ServiceSpec, _>(NS, Some(list.metadata.resource_version));
+        match events.next().await.expect("event")? {
+            WatchEvent::Resync(list) => assert_eq!(list.items.len(), 1002),
+            event => panic!("unexpected event: {event:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[test_async]
+    async fn test_fake_server_dynamic() -> Result<()> {
+        use k8_types::{ApiResource, DynamicObject, DynamicWatch};
+
+        let (_server, client) = start().await?;
+        let resource = ApiResource::from_spec::<ServiceSpec>();
+        assert_eq!(resource.api_version(), "v1");
+
+        let list = client.retrieve_dynamic_items(&resource, NS, None).await?;
+        let mut events =
+            client.watch_dynamic_stream_since(&resource, NS, Some(list.metadata.resource_version));
+
+        let input = DynamicObject::new("web", &resource)
+            .with_namespace(NS)
+            .with_data(serde_json::json!({"spec": {"ports": [{"port": 80}]}}));
+        let created = client.create_dynamic_item(&resource, &input).await?;
+        assert!(!created.metadata.uid.is_empty());
+        assert_eq!(created.data["spec"]["ports"][0]["port"], 80);
+
+        // same object is visible thru typed api
+        let service = client
+            .retrieve_item::<ServiceSpec, _>(&InputObjectMeta::named("web", NS))
+            .await?
+            .expect("service");
+        assert_eq!(service.spec.ports[0].port, 80);
+
+        let patched = client
+            .patch_dynamic_item(
+                &resource,
+                "web",
+                NS,
+                &serde_json::json!({"metadata": {"labels": {"tier": "frontend"}}}),
+                PatchMergeType::JsonMerge,
+            )
+            .await?;
+        assert_eq!(patched.metadata.labels["tier"], "frontend");
+
+        let items = client
+            .retrieve_dynamic_items(
+                &resource,
+                NS,
+                Some(ListArg {
+                    label_selector: Some("tier=frontend".to_owned()),
+                    ..Default::default()
+                }),
+            )
+            .await?;
+        assert_eq!(items.items.len(), 1);
+
+        let events = events.next().await.expect("event")?;
+        match events.into_iter().next().expect("event")? {
+            DynamicWatch::ADDED(obj) => assert_eq!(obj.metadata.name, "web"),
+            event => panic!("unexpected event: {event:?}"),
+        }
+
+        assert!(client
+            .delete_dynamic_item(&resource, "web", NS, None)
+            .await?
+            .is_none());
+        assert!(client
+            .retrieve_dynamic_item(&resource, "web", NS)
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[test_async]
+    async fn test_fake_server_discovery() -> Result<()> {
+        use k8_types::coordination::lease::LeaseSpec;
+
+        let server = FakeApiServer::builder()
+            .register::<PodSpec>()
+            .register::<LeaseSpec>()
+            .start()
+            .await?;
+        // discovery has its own limiter, with this limit it would take 10s for each request
+        let client = server.k8_client()?.with_rate_limit(0.1, 1);
+
+        let started = std::time::Instant::now();
+        let discovery = client.discovery().await?;
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        assert_eq!(
+            discovery.preferred_version("coordination.k8s.io"),
+            Some("v1")
+        );
+
+        let pods = discovery.resolve("pod").expect("pods");
+        assert_eq!(pods.resource.plural, "pods");
+        assert!(pods.resource.namespaced);
+        assert!(pods.supports("watch"));
+
+        let leases = discovery.resolve("Lease").expect("leases");
+        assert_eq!(leases.resource.api_version(), "coordination.k8s.io/v1");
+        assert!(discovery
+            .resource("coordination.k8s.io", "v1", "Lease")
+            .is_some());
+        assert!(discovery.resolve("services").is_none());
+
+        // discovery is cached
+        assert!(Arc::ptr_eq(&discovery, &client.discovery().await?));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "ws")]
+    #[test_async]
+    async fn test_fake_server_exec() -> Result<()> {
+        use futures_util::io::AsyncWriteExt;
+        use k8_client::fake_server::FakeExecOutput;
+
+        let (server, client) = start().await?;
+        server.set_exec(NS, "pod1", |command, stdin| match command {
+            [cat] if cat == "cat" => FakeExecOutput {
+                stdout: stdin.to_vec(),
+                ..Default::default()
+            },
+            _ => FakeExecOutput {
+                stderr: format!("unknown command: {}", command.join(" ")).into_bytes(),
+                exit_code: 127,
+                ..Default::default()
+            },
+        });
+
+        let mut process = client
+            .exec(NS, "pod1", "main", ["cat"], true, false)
+            .await?;
+        let mut stdin = process.stdin().expect("stdin");
+        stdin.write_all(b"hello").await?;
+        stdin.close().await?;
+        let mut stdout = String::new();
+        process
+            .stdout()
+            .expect("stdout")
+            .read_to_string(&mut stdout)
+            .await?;
+        assert_eq!(stdout, "hello");
+        assert!(process.status().await?.success());
+
+        // writes wait while outgoing buffer is full
+        let mut process = client
+            .exec(NS, "pod1", "main", ["cat"], true, false)
+            .await?;
+        let mut stdin = process.stdin().expect("stdin");
+        let chunk = [b'x'; 1024];
+        for _ in 0..1024 {
+            stdin.write_all(&chunk).await?;
+        }
+        stdin.close().await?;
+        let mut stdout = vec![];
+        process
+            .stdout()
+            .expect("stdout")
+            .read_to_end(&mut stdout)
+            .await?;
+        assert_eq!(stdout.len(), 1024 * 1024);
+        assert!(process.status().await?.success());
+
+        let mut process = client
+            .exec(NS, "pod1", "main", ["ls", "-l /data"], true, false)
+            .await?;
+        process.stdin().expect("stdin").close().await?;
+        let mut stderr = String::new();
+        process
+            .stderr()
+            .expect("stderr")
+            .read_to_string(&mut stderr)
+            .await?;
+        assert_eq!(stderr, "unknown command: ls -l /data");
+        assert_eq!(process.status().await?.exit_code, Some(127));
+
+        // without stdin, command runs without waiting for input
+        let mut process = client
+            .exec(NS, "pod1", "main", ["cat"], false, false)
+            .await?;
+        assert!(process.stdin().is_none());
+        let mut stdout = String::new();
+        process
+            .stdout()
+            .expect("stdout")
+            .read_to_string(&mut stdout)
+            .await?;
+        assert_eq!(stdout, "");
+        assert!(process.status().await?.success());
+
+        // connection is closed when process is dropped, so server stops reading stdin
+        let (ran_sender, ran) = std::sync::mpsc::channel();
+        let ran_sender = std::sync::Mutex::new(ran_sender);
+        server.set_exec(NS, "pod1", move |_, _| {
+            let _ = ran_sender.lock().expect("lock").send(());
+            FakeExecOutput::default()
+        });
+        drop(
+            client
+                .exec(NS, "pod1", "main", ["sleep"], true, false)
+                .await?,
+        );
+        let mut closed = false;
+        for _ in 0..100 {
+            if ran.try_recv().is_ok() {
+                closed = true;
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert!(closed, "connection was not closed");
+
+        let err = client
+            .exec(NS, "pod2", "main", ["ls"], true, false)
+            .await
+            .err()
+            .expect("not found");
+        assert_eq!(status_code(err), Some(404));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "ws")]
+    #[test_async]
+    async fn test_fake_server_port_forward() -> Result<()> {
+        use futures_util::io::AsyncWriteExt;
+        use fluvio_future::net::{TcpListener, TcpStream};
+        use fluvio_future::task::spawn;
+
+        // echo server standing in for container port, reports when connection is closed
+        let echo = TcpListener::bind("127.0.0.1:0").await?;
+        let echo_address = echo.local_addr()?;
+        let (echo_closed_sender, echo_closed) = std::sync::mpsc::channel();
+        spawn(async move {
+            while let Ok((stream, _)) = echo.accept().await {
+                let echo_closed = echo_closed_sender.clone();
+                spawn(async move {
+                    let mut writer = stream.clone();
+                    let _ = futures_util::io::copy(stream, &mut writer).await;
+                    let _ = echo_closed.send(());
+                });
+            }
+        });
+
+        let (server, client) = start().await?;
+        server.set_port_forward(NS, "pod1", 80, echo_address);
+        server.set_port_forward(NS, "pod1", 82, echo_address);
+
+        let mut forward = client.port_forward(NS, "pod1", &[80, 81, 82]).await?;
+        assert_eq!(forward.ports(), &[80, 81, 82]);
+        let mut stream = forward.take_stream(80).expect("stream");
+        assert!(forward.take_stream(80).is_none());
+        stream.write_all(b"hello").await?;
+        let mut buf = [0; 5];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"hello");
+
+        // port without target is refused
+        let mut refused = forward.take_stream(81).expect("stream");
+        assert!(refused.read(&mut buf).await.is_err());
+
+        // closing stream doesn't close other ports
+        let mut other = forward.take_stream(82).expect("stream");
+        stream.close().await?;
+        assert!(stream.write_all(b"closed").await.is_err());
+        other.write_all(b"world").await?;
+        other.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"world");
+
+        // connection is closed when all streams are closed or dropped
+        drop((forward, refused));
+        other.close().await?;
+        let mut closed = 0;
+        for _ in 0..100 {
+            closed += echo_closed.try_iter().count();
+            if closed == 2 {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(closed, 2, "connection was not closed");
+
+        let listener_client = Arc::new(client);
+        let listener = listener_client
+            .clone()
+            .port_forward_listener(NS, "pod1", 80, "127.0.0.1:0".parse()?)
+            .await?;
+        for message in [b"ping", b"pong"] {
+            let mut local = TcpStream::connect(listener.local_addr()).await?;
+            local.write_all(message).await?;
+            let mut buf = [0; 4];
+            local.read_exact(&mut buf).await?;
+            assert_eq!(&buf, message);
+        }
+
+        // response is received after local side stops writing
+        let delayed = TcpListener::bind("127.0.0.1:0").await?;
+        server.set_port_forward(NS, "pod1", 83, delayed.local_addr()?);
+        spawn(async move {
+            while let Ok((mut stream, _)) = delayed.accept().await {
+                spawn(async move {
+                    let mut buf = [0; 4];
+                    stream.read_exact(&mut buf).await?;
+                    sleep(Duration::from_millis(100)).await;
+                    stream.write_all(&buf).await
+                });
+            }
+        });
+        let listener = listener_client
+            .clone()
+            .port_forward_listener(NS, "pod1", 83, "127.0.0.1:0".parse()?)
+            .await?;
+        let mut local = TcpStream::connect(listener.local_addr()).await?;
+        local.write_all(b"half").await?;
+        local.shutdown(std::net::Shutdown::Write)?;
+        let mut buf = [0; 4];
+        local.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"half");
+
+        let client = server.k8_client()?;
+        let err = client
+            .port_forward(NS, "pod2", &[80])
+            .await
+            .err()
+            .expect("not found");
+        assert_eq!(status_code(err), Some(404));
+
+        Ok(())
+    }
+
+    #[test_async]
+    async fn test_fake_server_log() -> Result<()> {
+        let (server, client) = start().await?;
+        server.set_log(NS, "pod1", "main", "first\nsecond\n");
+
+        let mut log = String::new();
+        client
+            .retrieve_log(NS, "pod1", "main")
+            .await?
+            .read_to_string(&mut log)
+            .await?;
+        assert_eq!(log, "first\nsecond\n");
+
+        Ok(())
+    }
+}
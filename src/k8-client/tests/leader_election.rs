@@ -0,0 +1,98 @@
+#[cfg(feature = "memory_client")]
+mod memory_tests {
+
+    use std::time::Duration;
+
+    use futures_util::StreamExt;
+
+    use fluvio_future::test_async;
+    use fluvio_future::timer::sleep;
+    use k8_client::memory::MemoryClient;
+    use k8_client::{LeaderElector, LeaderEvent};
+    use k8_metadata_client::MetadataClient;
+    use k8_types::coordination::lease::LeaseSpec;
+    use k8_types::InputObjectMeta;
+
+    const NS: &str = "default";
+    const LEASE: &str = "my-lock";
+
+    #[test_async]
+    async fn test_leader_failover() -> anyhow::Result<()> {
+        let client = MemoryClient::new_shared();
+        let lease_duration = Duration::from_millis(100);
+
+        let mut a =
+            LeaderElector::new(client.clone(), NS, LEASE, "a").with_lease_duration(lease_duration);
+        let mut b =
+            LeaderElector::new(client.clone(), NS, LEASE, "b").with_lease_duration(lease_duration);
+
+        assert!(a.try_acquire_or_renew().await?);
+        assert!(!b.try_acquire_or_renew().await?);
+        assert_eq!(b.leader(), Some("a"));
+
+        // renewal is observed by b, so lease is not expired
+        sleep(lease_duration / 2).await;
+        assert!(a.try_acquire_or_renew().await?);
+        sleep(lease_duration / 2).await;
+        assert!(!b.try_acquire_or_renew().await?);
+
+        // a stops renewing
+        sleep(lease_duration).await;
+        assert!(b.try_acquire_or_renew().await?);
+        assert!(!a.try_acquire_or_renew().await?);
+        assert!(!a.is_leader());
+        assert_eq!(a.leader(), Some("b"));
+
+        let lease = client
+            .retrieve_item::<LeaseSpec, _>(&InputObjectMeta::named(LEASE, NS))
+            .await?
+            .expect("lease");
+        assert_eq!(lease.spec.holder_identity.as_deref(), Some("b"));
+        assert_eq!(lease.spec.lease_transitions, Some(1));
+
+        // released lease can be acquired without waiting
+        b.release().await?;
+        assert!(!b.is_leader());
+        assert!(a.try_acquire_or_renew().await?);
+        Ok(())
+    }
+
+    #[test_async]
+    async fn test_leader_stale_update() -> anyhow::Result<()> {
+        let client = MemoryClient::new_shared();
+
+        let mut a = LeaderElector::new(client.clone(), NS, LEASE.to_owned(), "a");
+        assert!(a.try_acquire_or_renew().await?);
+
+        let meta = InputObjectMeta::named(LEASE, NS);
+        let lease = client
+            .retrieve_item::<LeaseSpec, _>(&meta)
+            .await?
+            .expect("lease");
+        assert!(a.try_acquire_or_renew().await?);
+
+        // update based on old version is rejected
+        let mut stale = lease.as_update();
+        stale.spec.holder_identity = Some("b".to_owned());
+        assert!(client.replace_item(stale).await.is_err());
+        Ok(())
+    }
+
+    #[test_async]
+    async fn test_leader_events() -> anyhow::Result<()> {
+        let client = MemoryClient::new_shared();
+
+        let mut a = LeaderElector::new(client.clone(), NS, LEASE, "a")
+            .with_retry_period(Duration::from_millis(10));
+        let mut b = LeaderElector::new(client.clone(), NS, LEASE, "b")
+            .with_retry_period(Duration::from_millis(10))
+            .into_stream();
+
+        assert!(a.try_acquire_or_renew().await?);
+        assert_eq!(b.next().await, Some(LeaderEvent::NewLeader("a".to_owned())));
+
+        a.release().await?;
+        assert_eq!(b.next().await, Some(LeaderEvent::Acquired));
+        Ok(())
+    }
+}
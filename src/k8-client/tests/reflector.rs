@@ -0,0 +1,78 @@
+#[cfg(feature = "memory_client")]
+mod memory_tests {
+
+    use futures_util::StreamExt;
+    use serde::{Deserialize, Serialize};
+
+    use fluvio_future::test_async;
+    use k8_client::memory::MemoryClient;
+    use k8_metadata_client::{MetadataClient, Reflector, ReflectorEvent};
+    use k8_types::{Crd, CrdNames, DefaultHeader, K8Obj, Spec, Status};
+
+    #[derive(Serialize, Deserialize, Default, Debug, Eq, PartialEq, Clone)]
+    struct MySpec {
+        value: i32,
+    }
+
+    #[derive(Serialize, Deserialize, Default, Debug, Eq, PartialEq, Clone)]
+    struct MySpecStatus {
+        value: i32,
+    }
+
+    impl Status for MySpecStatus {}
+
+    impl Spec for MySpec {
+        type Status = MySpecStatus;
+        type Header = DefaultHeader;
+
+        fn metadata() -> &'static Crd {
+            &Crd {
+                group: "test.fluvio",
+                version: "v1",
+                names: CrdNames {
+                    kind: "myspec",
+                    plural: "myspecs",
+                    singular: "myspec",
+                },
+            }
+        }
+    }
+
+    #[test_async]
+    async fn test_reflector_store() -> anyhow::Result<()> {
+        let client = MemoryClient::new_shared();
+
+        let reflector = Reflector::<MySpec, _>::new(client.clone(), "");
+        let store = reflector.store();
+        assert!(!store.has_synced());
+
+        let mut events = reflector.into_stream();
+
+        let Some(ReflectorEvent::Synced(items)) = events.next().await else {
+            panic!("expected synced");
+        };
+        assert!(items.is_empty());
+        store.wait_until_synced().await;
+        assert!(store.has_synced());
+
+        client
+            .create_item(K8Obj::new("one", MySpec { value: 1 }).as_input())
+            .await?;
+        let Some(ReflectorEvent::Added(obj)) = events.next().await else {
+            panic!("expected added");
+        };
+        assert_eq!(obj.spec.value, 1);
+        let one = obj.metadata.as_item();
+        assert_eq!(store.get(&one).await.expect("one").spec.value, 1);
+        assert_eq!(store.len().await, 1);
+
+        client.delete_item::<MySpec, _>(&one).await?;
+        let Some(ReflectorEvent::Deleted(_)) = events.next().await else {
+            panic!("expected deleted");
+        };
+        assert!(store.get(&one).await.is_none());
+        assert!(store.is_empty().await);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+#[cfg(feature = "k8")]
+mod integration_tests {
+
+    use anyhow::Result;
+    use futures_util::StreamExt;
+    use rand::distributions::Alphanumeric;
+    use rand::{thread_rng, Rng};
+
+    use fluvio_future::test_async;
+    use k8_client::{K8Client, WatchEvent};
+    use k8_metadata_client::MetadataClient;
+    use k8_types::core::config_map::ConfigMapSpec;
+    use k8_types::{InputK8Obj, InputObjectMeta, K8Watch, Spec};
+
+    const NS: &str = "default";
+
+    fn create_client() -> K8Client {
+        K8Client::try_default().expect("cluster not initialized")
+    }
+
+    fn new_config_map() -> InputK8Obj<ConfigMapSpec> {
+        let rname: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .map(char::from)
+            .take(5)
+            .collect();
+
+        InputK8Obj {
+            api_version: ConfigMapSpec::api_version(),
+            kind: ConfigMapSpec::kind(),
+            metadata: InputObjectMeta::named(
+                format!("watch{}", rname.to_lowercase()),
+                NS.to_owned(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test_async]
+    async fn test_resilient_watch_events() -> Result<()> {
+        let client = create_client();
+        let list = client.retrieve_items::<ConfigMapSpec, _>(NS).await?;
+
+        let mut stream = client
+            .watch_stream_resilient::<ConfigMapSpec, _>(NS, Some(list.metadata.resource_version));
+
+        let created = client.create_item(new_config_map()).await?;
+
+        let event = stream.next().await.expect("event")?;
+        let WatchEvent::Event(K8Watch::ADDED(obj)) = event else {
+            panic!("expected added, got: {:#?}", event);
+        };
+        assert_eq!(obj.metadata.name, created.metadata.name);
+
+        client
+            .delete_item::<ConfigMapSpec, _>(&created.metadata)
+            .await?;
+        Ok(())
+    }
+
+    #[test_async]
+    async fn test_resilient_watch_expired_version() -> Result<()> {
+        let client = create_client();
+
+        // version this old has been compacted away
+        let mut stream =
+            client.watch_stream_resilient::<ConfigMapSpec, _>(NS, Some("1".to_owned()));
+
+        let event = stream.next().await.expect("event")?;
+        let WatchEvent::Resync(list) = event else {
+            panic!("expected resync, got: {:#?}", event);
+        };
+        assert!(!list.metadata.resource_version.is_empty());
+        Ok(())
+    }
+}
@@ -8,9 +8,8 @@ use tracing::trace;
 const BASE_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
 const API_SERVER: &str = "https://kubernetes.default.svc";
 
-///var/run/secrets/kubernetes.io/serviceaccount
-
-/// Configuration as Pod
+/// Configuration as Pod, loaded from service account
+/// mounted at `/var/run/secrets/kubernetes.io/serviceaccount`
 #[derive(Debug, Default, Clone)]
 pub struct PodConfig {
     pub namespace: String,
@@ -1,10 +1,11 @@
 use std::fmt::Display;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use futures_util::future::ready;
-use futures_util::future::FutureExt;
+use futures_util::future::{Future, FutureExt};
 use futures_util::stream::once;
 use futures_util::stream::BoxStream;
 use futures_util::stream::StreamExt;
@@ -14,11 +15,61 @@ use serde_json::Value;
 use tracing::debug;
 use tracing::trace;
 
+use fluvio_future::retry::FibonacciBackoff;
+use fluvio_future::timer::sleep;
 use k8_diff::{Changes, Diff};
-use k8_types::{InputK8Obj, K8List, K8Meta, K8Obj, DeleteStatus, K8Watch, Spec, UpdateK8ObjStatus};
-use k8_types::options::DeleteOptions;
-use crate::diff::PatchMergeType;
-use crate::{ApplyResult, DiffableK8Obj};
+use k8_types::{
+    InputK8Obj, K8List, K8Meta, K8Obj, DeleteStatus, FieldSelector, K8Watch, Selector, Spec,
+    UpdateK8ObjStatus, UpdatedK8Obj,
+};
+use k8_types::options::{DeleteOptions, ListOptions, ResourceVersionMatch};
+use crate::diff::{apply_patch, ApplyOptions, PatchMergeType};
+use crate::{ApplyResult, DiffableK8Obj, K8ErrorExt};
+
+/// number of times update is retried after conflict
+const CONFLICT_RETRIES: usize = 5;
+
+/// delays between retries of conflicting update
+fn conflict_backoff() -> impl Iterator<Item = Duration> + Send {
+    FibonacciBackoff::from_millis(10)
+        .max_delay(Duration::from_secs(1))
+        .take(CONFLICT_RETRIES)
+}
+
+/// retrieve object, change it by update and write it, repeating while write conflicts
+async fn write_on_conflict<C, S, M, F, W, Fut>(
+    client: &C,
+    metadata: &M,
+    mut update: F,
+    write: W,
+) -> Result<K8Obj<S>>
+where
+    C: MetadataClient + ?Sized,
+    S: Spec,
+    M: K8Meta + Display + Send + Sync,
+    F: FnMut(&mut K8Obj<S>) + Send,
+    W: Fn(K8Obj<S>) -> Fut + Send,
+    Fut: Future<Output = Result<K8Obj<S>>> + Send,
+{
+    let mut backoff = conflict_backoff();
+    loop {
+        let mut k8_obj = client
+            .retrieve_item::<S, M>(metadata)
+            .await?
+            .ok_or_else(|| ObjectKeyNotFound::new(metadata.to_string()))?;
+        update(&mut k8_obj);
+        match write(k8_obj).await {
+            Err(err) if err.is_conflict() => match backoff.next() {
+                Some(delay) => {
+                    debug!(%metadata, ?delay, "{}: conflict, retrying update", S::label());
+                    sleep(delay).await;
+                }
+                None => return Err(err),
+            },
+            result => return result,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub enum NameSpace {
@@ -70,11 +121,81 @@ impl From<&str> for NameSpace {
     }
 }
 
+/// options of list request, see [ListOptions]
 #[derive(Default, Clone)]
 pub struct ListArg {
     pub field_selector: Option<String>,
-    pub include_uninitialized: Option<bool>,
     pub label_selector: Option<String>,
+    /// maximum number of items, rest can be retrieved with continue token
+    pub limit: Option<u32>,
+    /// continue token of previous chunk
+    pub continu: Option<String>,
+    /// `0` means any version, which may be served from cache of API server
+    pub resource_version: Option<String>,
+    pub resource_version_match: Option<ResourceVersionMatch>,
+    pub timeout_seconds: Option<u32>,
+    pub allow_watch_bookmarks: Option<bool>,
+}
+
+impl ListArg {
+    /// select objects by labels
+    pub fn with_label_selector(mut self, selector: &Selector) -> Self {
+        self.label_selector = Some(selector.to_string());
+        self
+    }
+
+    /// select objects by fields
+    pub fn with_field_selector(mut self, selector: &FieldSelector) -> Self {
+        self.field_selector = Some(selector.to_string());
+        self
+    }
+
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn with_continue(mut self, continu: impl Into<String>) -> Self {
+        self.continu = Some(continu.into());
+        self
+    }
+
+    /// read at resource version, how it is interpreted depends on [ResourceVersionMatch]
+    pub fn with_resource_version(
+        mut self,
+        resource_version: impl Into<String>,
+        version_match: Option<ResourceVersionMatch>,
+    ) -> Self {
+        self.resource_version = Some(resource_version.into());
+        self.resource_version_match = version_match;
+        self
+    }
+
+    pub fn with_timeout_seconds(mut self, timeout_seconds: u32) -> Self {
+        self.timeout_seconds = Some(timeout_seconds);
+        self
+    }
+
+    pub fn with_watch_bookmarks(mut self) -> Self {
+        self.allow_watch_bookmarks = Some(true);
+        self
+    }
+}
+
+impl From<ListArg> for ListOptions {
+    fn from(arg: ListArg) -> Self {
+        Self {
+            continu: arg.continu,
+            field_selector: arg.field_selector,
+            label_selector: arg.label_selector,
+            limit: arg.limit,
+            resource_version: arg.resource_version,
+            resource_version_match: arg.resource_version_match,
+            timeout_seconds: arg.timeout_seconds,
+            allow_watch_bookmarks: arg.allow_watch_bookmarks,
+            ..Default::default()
+        }
+    }
 }
 
 // For error mapping: see: https://doc.rust-lang.org/nightly/core/convert/trait.From.html
@@ -151,6 +272,13 @@ pub trait MetadataClient: Send + Sync {
     where
         S: Spec;
 
+    /// replace existing object, fields missing from object are removed as with PUT.
+    /// if resource version is set, object is only replaced if it matches current version,
+    /// otherwise conflict error is returned
+    async fn replace_item<S>(&self, value: UpdatedK8Obj<S>) -> Result<K8Obj<S>>
+    where
+        S: Spec;
+
     /// apply object, this is similar to ```kubectl apply```
     /// for now, this doesn't do any optimization
     /// if object doesn't exist, it will be created
@@ -207,11 +335,56 @@ pub trait MetadataClient: Send + Sync {
         }
     }
 
+    /// apply object using server side apply, object is created if it doesn't exist.
+    /// if fields are owned by other managers, [crate::ApplyConflict] is returned unless apply is forced.
+    /// default implementation sends apply patch with [Self::patch]
+    async fn server_side_apply<S>(
+        &self,
+        value: InputK8Obj<S>,
+        options: ApplyOptions,
+    ) -> Result<K8Obj<S>>
+    where
+        S: Spec,
+    {
+        let patch = apply_patch(&value)?;
+        self.patch(&value.metadata, &patch, PatchMergeType::Apply(options))
+            .await
+    }
+
     /// update status
     async fn update_status<S>(&self, value: &UpdateK8ObjStatus<S>) -> Result<K8Obj<S>>
     where
         S: Spec;
 
+    /// read-modify-write of object, this is similar to client-go `RetryOnConflict`.
+    /// object is retrieved, changed by update and replaced.
+    /// if object was modified in the meantime, it is retrieved again and update is repeated
+    async fn retry_on_conflict<S, M, F>(&self, metadata: &M, update: F) -> Result<K8Obj<S>>
+    where
+        S: Spec,
+        M: K8Meta + Display + Send + Sync,
+        F: FnMut(&mut K8Obj<S>) + Send,
+    {
+        write_on_conflict(self, metadata, update, |k8_obj| async move {
+            self.replace_item(k8_obj.as_update()).await
+        })
+        .await
+    }
+
+    /// same as [Self::retry_on_conflict] but status changed by update is written using [Self::update_status]
+    async fn retry_status_on_conflict<S, M, F>(&self, metadata: &M, update: F) -> Result<K8Obj<S>>
+    where
+        S: Spec,
+        M: K8Meta + Display + Send + Sync,
+        F: FnMut(&mut K8Obj<S>) + Send,
+    {
+        write_on_conflict(self, metadata, update, |k8_obj| async move {
+            self.update_status(&k8_obj.as_status_update(k8_obj.status.clone()))
+                .await
+        })
+        .await
+    }
+
     /// patch existing obj
     async fn patch_obj<S, M>(&self, metadata: &M, patch: &Value) -> Result<K8Obj<S>>
     where
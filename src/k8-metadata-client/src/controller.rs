@@ -0,0 +1,177 @@
+//!
+//! # Controller
+//!
+//! Runs reconcile loop for objects of single spec.
+//! Changes observed by [Reflector] are queued into [WorkQueue] keyed by object's namespace and name.
+//! Keys are handed out to reconcile function with bounded concurrency.
+//!
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures_util::future::{self, ready};
+use futures_util::pin_mut;
+use futures_util::stream::{self, StreamExt};
+use tracing::{debug, error, trace};
+
+use k8_types::{ItemMeta, K8Obj, Spec};
+
+use crate::queue::WorkQueue;
+use crate::reflector::{Reflector, ReflectorEvent, ReflectorStore};
+use crate::{MetadataClient, NameSpace, SharedClient};
+
+const DEFAULT_CONCURRENCY: usize = 1;
+
+/// result of successful reconcile
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Action {
+    /// reconcile again after duration even if object hasn't changed
+    Requeue(Duration),
+    /// wait until object changes
+    AwaitChange,
+}
+
+/// Controller for objects of spec `S`
+///
+/// ```ignore
+/// Controller::<TopicSpec, _>::new(client, "default")
+///     .with_concurrency(4)
+///     .run(|topic| async move {
+///         // bring cluster state closer to topic spec
+///         Ok(Action::AwaitChange)
+///     })
+///     .await;
+/// ```
+pub struct Controller<S, C>
+where
+    S: Spec,
+{
+    reflector: Reflector<S, C>,
+    queue: Arc<WorkQueue<ItemMeta>>,
+    concurrency: usize,
+}
+
+impl<S, C> Controller<S, C>
+where
+    S: Spec + 'static,
+    C: MetadataClient + 'static,
+{
+    pub fn new<N>(client: SharedClient<C>, namespace: N) -> Self
+    where
+        N: Into<NameSpace>,
+    {
+        Self::with_reflector(Reflector::new(client, namespace))
+    }
+
+    pub fn with_reflector(reflector: Reflector<S, C>) -> Self {
+        Self {
+            reflector,
+            queue: Arc::new(WorkQueue::default()),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// maximum number of reconcile running at same time
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// backoff applied to key when reconcile fails, doubled at each consecutive failure
+    pub fn with_backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.queue = Arc::new(WorkQueue::new(base_delay, max_delay));
+        self
+    }
+
+    /// objects observed by controller
+    pub fn store(&self) -> ReflectorStore<S> {
+        self.reflector.store()
+    }
+
+    /// queue of keys to reconcile. Can be used to trigger reconcile from other sources
+    pub fn queue(&self) -> Arc<WorkQueue<ItemMeta>> {
+        self.queue.clone()
+    }
+
+    /// run until change stream ends.
+    /// reconcile is not called for deleted objects
+    pub async fn run<F, Fut>(self, reconcile: F)
+    where
+        F: Fn(K8Obj<S>) -> Fut,
+        Fut: Future<Output = Result<Action>>,
+    {
+        let store = self.reflector.store();
+        let queue = self.queue;
+        let reconcile = &reconcile;
+
+        let watcher = self.reflector.into_stream().for_each(|event| {
+            let queue = queue.clone();
+            async move {
+                match event {
+                    ReflectorEvent::Synced(items) => {
+                        for obj in items {
+                            queue.add(obj.metadata.as_item()).await;
+                        }
+                    }
+                    ReflectorEvent::Added(obj)
+                    | ReflectorEvent::Modified(obj)
+                    | ReflectorEvent::Deleted(obj) => {
+                        queue.add(obj.metadata.as_item()).await;
+                    }
+                }
+            }
+        });
+
+        let workers = stream::unfold(queue.clone(), |queue| async move {
+            let key = queue.next().await;
+            Some((key, queue))
+        })
+        .map(|key| {
+            let store = store.clone();
+            let queue = queue.clone();
+            async move {
+                Self::reconcile_key(&store, &queue, &key, reconcile).await;
+                queue.done(&key).await;
+            }
+        })
+        .buffer_unordered(self.concurrency)
+        .for_each(|_| ready(()));
+
+        pin_mut!(watcher);
+        pin_mut!(workers);
+        future::select(watcher, workers).await;
+        debug!("{}: controller terminated", S::label());
+    }
+
+    async fn reconcile_key<F, Fut>(
+        store: &ReflectorStore<S>,
+        queue: &WorkQueue<ItemMeta>,
+        key: &ItemMeta,
+        reconcile: &F,
+    ) where
+        F: Fn(K8Obj<S>) -> Fut,
+        Fut: Future<Output = Result<Action>>,
+    {
+        let Some(obj) = store.get(key).await else {
+            trace!(%key, "{}: object is gone, skipping", S::label());
+            queue.forget(key).await;
+            return;
+        };
+
+        match reconcile(obj).await {
+            Ok(Action::Requeue(delay)) => {
+                trace!(%key, ?delay, "{}: requeue", S::label());
+                queue.forget(key).await;
+                queue.add_after(key.clone(), delay).await;
+            }
+            Ok(Action::AwaitChange) => {
+                queue.forget(key).await;
+            }
+            Err(err) => {
+                error!(%key, %err, "{}: reconcile failed", S::label());
+                queue.add_rate_limited(key.clone()).await;
+            }
+        }
+    }
+}
@@ -1,6 +1,12 @@
+use std::fmt;
+
+use anyhow::Result;
 use serde::Serialize;
+use serde_json::Value;
+
+use k8_types::{Crd, InputK8Obj, K8Obj, MetaStatus, Spec};
 
-use k8_types::{Crd, K8Obj, Spec};
+const CONFLICT: u16 = 409;
 
 #[derive(Debug)]
 pub enum ApplyResult<S>
@@ -12,13 +18,126 @@ where
     Patched(K8Obj<S>),
 }
 
-#[derive(Debug, Serialize)]
+/// options for server side apply.
+/// field manager is required by server when applying
+#[derive(Debug, Default, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ApplyOptions {
     pub force: bool,
     pub field_manager: Option<String>,
 }
 
+impl ApplyOptions {
+    pub fn new<M: Into<String>>(field_manager: M) -> Self {
+        Self {
+            force: false,
+            field_manager: Some(field_manager.into()),
+        }
+    }
+
+    /// take ownership of fields managed by other managers instead of failing with conflict
+    pub fn force(mut self) -> Self {
+        self.force = true;
+        self
+    }
+}
+
+/// field owned by other manager which prevents apply
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FieldConflict {
+    /// path of field, ex: `.spec.replicas`
+    pub field: String,
+    /// message of server, which names manager owning the field
+    pub message: String,
+}
+
+/// server side apply failed because fields are managed by other managers.
+/// apply can be retried with [ApplyOptions::force] to take ownership
+#[derive(Debug, Clone)]
+pub struct ApplyConflict {
+    pub conflicts: Vec<FieldConflict>,
+    pub status: MetaStatus,
+}
+
+impl ApplyConflict {
+    /// extract conflicts from conflict status, return None if status is not conflict
+    pub fn from_status(status: &MetaStatus) -> Option<Self> {
+        if status.code != Some(CONFLICT) {
+            return None;
+        }
+        let conflicts = status
+            .details
+            .iter()
+            .flat_map(|details| details.causes.iter())
+            .filter_map(|cause| {
+                Some(FieldConflict {
+                    field: cause.field.clone()?,
+                    message: cause.message.clone().unwrap_or_default(),
+                })
+            })
+            .collect();
+        Some(Self {
+            conflicts,
+            status: status.clone(),
+        })
+    }
+
+    /// paths of conflicting fields
+    pub fn fields(&self) -> Vec<&str> {
+        self.conflicts
+            .iter()
+            .map(|conflict| conflict.field.as_str())
+            .collect()
+    }
+}
+
+impl fmt::Display for ApplyConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "apply conflict on fields: {}", self.fields().join(", "))
+    }
+}
+
+impl std::error::Error for ApplyConflict {}
+
+/// body for server side apply.
+/// null and empty metadata fields are removed so that apply doesn't claim ownership of them.
+/// empty spec is removed as well, since some kinds such as ConfigMap don't have spec
+pub fn apply_patch<S>(value: &InputK8Obj<S>) -> Result<Value>
+where
+    S: Spec,
+{
+    let mut body = serde_json::to_value(value)?;
+    remove_nulls(&mut body);
+    if let Value::Object(obj) = &mut body {
+        if obj
+            .get("spec")
+            .is_some_and(|spec| spec == &Value::Object(Default::default()))
+        {
+            obj.remove("spec");
+        }
+    }
+    if let Some(Value::Object(metadata)) = body.get_mut("metadata") {
+        metadata.retain(|_, field| match field {
+            Value::Array(items) => !items.is_empty(),
+            Value::Object(map) => !map.is_empty(),
+            Value::String(value) => !value.is_empty(),
+            _ => true,
+        });
+    }
+    Ok(body)
+}
+
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, field| !field.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
 #[allow(dead_code)]
 pub enum PatchMergeType {
     Json,
@@ -69,3 +188,23 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+
+    use k8_types::MetaStatus;
+
+    use super::ApplyConflict;
+
+    #[test]
+    fn test_apply_conflict() {
+        let data = r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"Apply failed with 1 conflict: conflict with \"kubectl\" using apps/v1: .spec.replicas","reason":"Conflict","details":{"causes":[{"reason":"FieldManagerConflict","message":"conflict with \"kubectl\" using apps/v1","field":".spec.replicas"}]},"code":409}"#;
+        let status: MetaStatus = serde_json::from_str(data).expect("status");
+        let conflict = ApplyConflict::from_status(&status).expect("conflict");
+        assert_eq!(conflict.fields(), vec![".spec.replicas"]);
+        assert_eq!(
+            conflict.conflicts[0].message,
+            "conflict with \"kubectl\" using apps/v1"
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use std::error::Error as StdError;
+use std::fmt;
+
+use k8_types::{MetaStatus, StatusCause};
+
+use crate::client::ObjectKeyNotFound;
+
+const UNAUTHORIZED: u16 = 401;
+const FORBIDDEN: u16 = 403;
+const NOT_FOUND: u16 = 404;
+const CONFLICT: u16 = 409;
+const GONE: u16 = 410;
+const UNPROCESSABLE_ENTITY: u16 = 422;
+const TOO_MANY_REQUESTS: u16 = 429;
+
+const ALREADY_EXISTS: &str = "AlreadyExists";
+
+/// Error returned by Kubernetes API.
+/// Failure status of API server is classified by code and reason, original status is kept.
+/// Clients return it inside `anyhow::Error`, use [K8ErrorExt] to inspect it.
+/// Errors with status are created by [K8Error::into_anyhow] so `downcast_ref::<MetaStatus>()`
+/// keeps working for code written before this type existed
+#[derive(Debug)]
+pub enum K8Error {
+    /// 401, credentials are missing or invalid
+    Unauthorized(MetaStatus),
+    /// 403, not allowed to perform operation
+    Forbidden(MetaStatus),
+    /// 404
+    NotFound(MetaStatus),
+    /// 409 when creating object which already exists
+    AlreadyExists(MetaStatus),
+    /// 409 when object was modified since it was read, or fields are owned by other manager
+    Conflict(MetaStatus),
+    /// 410, resource version is too old to watch from
+    Gone(MetaStatus),
+    /// 422, object failed validation. causes name invalid fields
+    Invalid {
+        status: MetaStatus,
+        causes: Vec<StatusCause>,
+    },
+    /// 429, server is throttling requests
+    TooManyRequests(MetaStatus),
+    /// other failure status
+    Status(MetaStatus),
+    /// server could not be reached or connection failed
+    Transport(Box<dyn StdError + Send + Sync>),
+    /// response could not be decoded
+    Decode(serde_json::Error),
+}
+
+impl K8Error {
+    pub fn transport<E>(err: E) -> Self
+    where
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        Self::Transport(err.into())
+    }
+
+    /// wrap in `anyhow::Error`. if there is status, it is inner error with this as context,
+    /// so error can be downcast to both [K8Error] and [MetaStatus]
+    pub fn into_anyhow(self) -> anyhow::Error {
+        match self.status().cloned() {
+            Some(status) => anyhow::Error::new(status).context(self),
+            None => anyhow::Error::new(self),
+        }
+    }
+
+    /// status returned by API server, None for transport and decode errors
+    pub fn status(&self) -> Option<&MetaStatus> {
+        match self {
+            Self::Unauthorized(status)
+            | Self::Forbidden(status)
+            | Self::NotFound(status)
+            | Self::AlreadyExists(status)
+            | Self::Conflict(status)
+            | Self::Gone(status)
+            | Self::Invalid { status, .. }
+            | Self::TooManyRequests(status)
+            | Self::Status(status) => Some(status),
+            Self::Transport(_) | Self::Decode(_) => None,
+        }
+    }
+
+    /// HTTP status code
+    pub fn code(&self) -> Option<u16> {
+        self.status().and_then(|status| status.code)
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::NotFound(_))
+    }
+
+    pub fn is_already_exists(&self) -> bool {
+        matches!(self, Self::AlreadyExists(_))
+    }
+
+    /// object was modified since it was read. creating existing object is not conflict
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, Self::Conflict(_))
+    }
+
+    pub fn is_gone(&self) -> bool {
+        matches!(self, Self::Gone(_))
+    }
+
+    pub fn is_forbidden(&self) -> bool {
+        matches!(self, Self::Forbidden(_))
+    }
+
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, Self::Unauthorized(_))
+    }
+
+    pub fn is_invalid(&self) -> bool {
+        matches!(self, Self::Invalid { .. })
+    }
+
+    pub fn is_too_many_requests(&self) -> bool {
+        matches!(self, Self::TooManyRequests(_))
+    }
+}
+
+impl From<MetaStatus> for K8Error {
+    fn from(status: MetaStatus) -> Self {
+        match status.code {
+            Some(UNAUTHORIZED) => Self::Unauthorized(status),
+            Some(FORBIDDEN) => Self::Forbidden(status),
+            Some(NOT_FOUND) => Self::NotFound(status),
+            Some(CONFLICT) if status.reason.as_deref() == Some(ALREADY_EXISTS) => {
+                Self::AlreadyExists(status)
+            }
+            Some(CONFLICT) => Self::Conflict(status),
+            Some(GONE) => Self::Gone(status),
+            Some(UNPROCESSABLE_ENTITY) => {
+                let causes = status
+                    .details
+                    .as_ref()
+                    .map(|details| details.causes.clone())
+                    .unwrap_or_default();
+                Self::Invalid { status, causes }
+            }
+            Some(TOO_MANY_REQUESTS) => Self::TooManyRequests(status),
+            _ => Self::Status(status),
+        }
+    }
+}
+
+impl From<serde_json::Error> for K8Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Decode(err)
+    }
+}
+
+impl fmt::Display for K8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Transport(err) => write!(f, "transport error: {}", err),
+            Self::Decode(err) => write!(f, "decode error: {}", err),
+            Self::Invalid { status, causes } => {
+                write!(f, "{}", status)?;
+                for cause in causes {
+                    write!(
+                        f,
+                        " {}: {}",
+                        cause.field.as_deref().unwrap_or_default(),
+                        cause.message.as_deref().unwrap_or_default()
+                    )?;
+                }
+                Ok(())
+            }
+            _ => match self.status() {
+                Some(status) => write!(f, "{}", status),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+impl StdError for K8Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Transport(err) => Some(err.as_ref()),
+            Self::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// inspect [K8Error] returned by clients
+///
+/// ```ignore
+/// match client.create_item(input).await {
+///     Err(err) if err.is_already_exists() => debug!("already created"),
+///     result => result?,
+/// }
+/// ```
+pub trait K8ErrorExt {
+    fn k8_error(&self) -> Option<&K8Error>;
+
+    fn is_not_found(&self) -> bool;
+
+    fn is_already_exists(&self) -> bool {
+        self.k8_error().is_some_and(K8Error::is_already_exists)
+    }
+
+    fn is_conflict(&self) -> bool {
+        self.k8_error().is_some_and(K8Error::is_conflict)
+    }
+
+    fn is_gone(&self) -> bool {
+        self.k8_error().is_some_and(K8Error::is_gone)
+    }
+
+    fn is_forbidden(&self) -> bool {
+        self.k8_error().is_some_and(K8Error::is_forbidden)
+    }
+
+    fn is_unauthorized(&self) -> bool {
+        self.k8_error().is_some_and(K8Error::is_unauthorized)
+    }
+
+    fn is_invalid(&self) -> bool {
+        self.k8_error().is_some_and(K8Error::is_invalid)
+    }
+
+    fn is_too_many_requests(&self) -> bool {
+        self.k8_error().is_some_and(K8Error::is_too_many_requests)
+    }
+}
+
+impl K8ErrorExt for anyhow::Error {
+    fn k8_error(&self) -> Option<&K8Error> {
+        self.downcast_ref()
+    }
+
+    fn is_not_found(&self) -> bool {
+        self.k8_error().is_some_and(K8Error::is_not_found) || self.is::<ObjectKeyNotFound>()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use k8_types::MetaStatus;
+
+    use super::{K8Error, K8ErrorExt};
+
+    fn status(data: &str) -> MetaStatus {
+        serde_json::from_str(data).expect("status")
+    }
+
+    #[test]
+    fn test_error_from_status() {
+        let err = K8Error::from(status(
+            r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"pods \"web\" not found","reason":"NotFound","details":{"name":"web","kind":"pods"},"code":404}"#,
+        ));
+        assert!(err.is_not_found());
+        assert_eq!(err.code(), Some(404));
+
+        let err = K8Error::from(status(
+            r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"services \"web\" already exists","reason":"AlreadyExists","details":{"name":"web","kind":"services"},"code":409}"#,
+        ));
+        assert!(err.is_already_exists());
+        assert!(!err.is_conflict());
+
+        let err = K8Error::from(status(
+            r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"Operation cannot be fulfilled on services \"web\": the object has been modified","reason":"Conflict","details":{"name":"web","kind":"services"},"code":409}"#,
+        ));
+        assert!(err.is_conflict());
+
+        let err = K8Error::from(status(
+            r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"too old resource version: 1 (48230)","reason":"Expired","code":410}"#,
+        ));
+        assert!(err.is_gone());
+    }
+
+    #[test]
+    fn test_invalid_causes() {
+        let err = K8Error::from(status(
+            r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"Service \"web\" is invalid: spec.ports[0].port: Invalid value: 0: must be between 1 and 65535, inclusive","reason":"Invalid","details":{"name":"web","kind":"Service","causes":[{"reason":"FieldValueInvalid","message":"Invalid value: 0: must be between 1 and 65535, inclusive","field":"spec.ports[0].port"}]},"code":422}"#,
+        ));
+        let K8Error::Invalid { causes, .. } = &err else {
+            panic!("expected invalid: {err:?}");
+        };
+        assert_eq!(causes[0].field.as_deref(), Some("spec.ports[0].port"));
+        assert!(err.to_string().contains("spec.ports[0].port"));
+    }
+
+    #[test]
+    fn test_anyhow_ext() {
+        let err: anyhow::Error = K8Error::from(status(
+            r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"pods \"web\" not found","reason":"NotFound","code":404}"#,
+        ))
+        .into();
+        assert!(err.is_not_found());
+        assert!(!err.is_conflict());
+        assert!(!err.is_forbidden());
+
+        let err = K8Error::from(status(
+            r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"services is forbidden: User \"dev\" cannot list resource \"services\"","reason":"Forbidden","details":{"kind":"services"},"code":403}"#,
+        ))
+        .into_anyhow();
+        assert!(err.is_forbidden());
+        assert!(!err.is_unauthorized());
+
+        let err: anyhow::Error = super::ObjectKeyNotFound::new("default/web".to_owned()).into();
+        assert!(err.is_not_found());
+        assert!(err.k8_error().is_none());
+
+        let err = anyhow::anyhow!("other");
+        assert!(!err.is_not_found());
+    }
+
+    #[test]
+    fn test_downcast_status() {
+        let err = K8Error::from(status(
+            r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"pods \"web\" not found","reason":"NotFound","code":404}"#,
+        ))
+        .into_anyhow();
+        assert!(err.is_not_found());
+        assert_eq!(
+            err.downcast_ref::<MetaStatus>()
+                .and_then(|status| status.code),
+            Some(404)
+        );
+        assert!(err.to_string().contains("not found"));
+
+        let err = K8Error::transport(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+            .into_anyhow();
+        assert!(err.k8_error().is_some());
+        assert!(err.downcast_ref::<MetaStatus>().is_none());
+    }
+}
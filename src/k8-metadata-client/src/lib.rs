@@ -1,6 +1,10 @@
 mod client;
+mod controller;
 mod diff;
+mod error;
 mod nothing;
+mod queue;
+mod reflector;
 pub use diff::*;
 
 pub use client::as_token_stream_result;
@@ -9,6 +13,10 @@ pub use client::MetadataClient;
 pub use client::NameSpace;
 pub use client::ObjectKeyNotFound;
 pub use client::TokenStreamResult;
+pub use controller::{Action, Controller};
+pub use error::{K8Error, K8ErrorExt};
 pub use nothing::DoNothingClient;
+pub use queue::WorkQueue;
+pub use reflector::{Reflector, ReflectorEvent, ReflectorStore};
 
 pub type SharedClient<C> = std::sync::Arc<C>;
@@ -12,10 +12,12 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value;
 
-use k8_types::{InputK8Obj, K8List, K8Meta, K8Obj, DeleteStatus, K8Watch, Spec, UpdateK8ObjStatus};
+use k8_types::{
+    InputK8Obj, K8List, K8Meta, K8Obj, DeleteStatus, K8Watch, Spec, UpdateK8ObjStatus, UpdatedK8Obj,
+};
 use k8_types::options::DeleteOptions;
 use crate::client::ObjectKeyNotFound;
-use crate::diff::PatchMergeType;
+use crate::diff::{ApplyOptions, PatchMergeType};
 
 use crate::{ListArg, MetadataClient, NameSpace, TokenStreamResult};
 
@@ -78,6 +80,24 @@ impl MetadataClient for DoNothingClient {
         Err(ObjectKeyNotFound::new(_value.metadata.name().into()).into())
     }
 
+    async fn replace_item<S>(&self, value: UpdatedK8Obj<S>) -> Result<K8Obj<S>>
+    where
+        S: Spec,
+    {
+        Err(ObjectKeyNotFound::new(value.metadata.name().into()).into())
+    }
+
+    async fn server_side_apply<S>(
+        &self,
+        value: InputK8Obj<S>,
+        _options: ApplyOptions,
+    ) -> Result<K8Obj<S>>
+    where
+        S: Spec,
+    {
+        Err(ObjectKeyNotFound::new(value.metadata.name().into()).into())
+    }
+
     async fn update_status<S>(&self, _value: &UpdateK8ObjStatus<S>) -> Result<K8Obj<S>>
     where
         UpdateK8ObjStatus<S>: Serialize + Debug,
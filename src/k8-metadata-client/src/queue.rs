@@ -0,0 +1,272 @@
+//!
+//! # Work Queue
+//!
+//! Deduplicating queue of keys to be processed.
+//! A key is handed out to a single worker at a time.  If it is added again while being processed,
+//! it is queued again once the worker calls [WorkQueue::done].
+//!
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use async_channel::{bounded, Receiver, Sender};
+use async_lock::Mutex;
+use futures_util::future::{select, Either};
+use tracing::trace;
+
+use fluvio_future::timer::sleep;
+
+const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(5);
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(1000);
+
+#[derive(Debug)]
+struct QueueState<K> {
+    queue: VecDeque<K>,
+    dirty: HashSet<K>,
+    processing: HashSet<K>,
+    delayed: HashMap<K, Instant>,
+    failures: HashMap<K, u32>,
+}
+
+impl<K> Default for QueueState<K> {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            dirty: HashSet::new(),
+            processing: HashSet::new(),
+            delayed: HashMap::new(),
+            failures: HashMap::new(),
+        }
+    }
+}
+
+impl<K> QueueState<K>
+where
+    K: Eq + Hash + Clone,
+{
+    /// returns true if key became available to workers
+    fn add(&mut self, key: K) -> bool {
+        if !self.dirty.insert(key.clone()) {
+            return false;
+        }
+        if self.processing.contains(&key) {
+            return false;
+        }
+        self.queue.push_back(key);
+        true
+    }
+
+    /// move delayed keys which are due into the queue, return earliest remaining deadline
+    fn promote(&mut self, now: Instant) -> Option<Instant> {
+        let due: Vec<K> = self
+            .delayed
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in due {
+            self.delayed.remove(&key);
+            self.add(key);
+        }
+        self.delayed.values().min().copied()
+    }
+}
+
+/// Queue of keys with deduplication, delayed requeue and per key exponential backoff
+#[derive(Debug)]
+pub struct WorkQueue<K> {
+    state: Mutex<QueueState<K>>,
+    notify_sender: Sender<()>,
+    notify_receiver: Receiver<()>,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl<K> Default for WorkQueue<K>
+where
+    K: Eq + Hash + Clone + Debug,
+{
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_DELAY, DEFAULT_MAX_DELAY)
+    }
+}
+
+impl<K> WorkQueue<K>
+where
+    K: Eq + Hash + Clone + Debug,
+{
+    /// base and max delay are used to compute backoff for [WorkQueue::add_rate_limited]
+    pub fn new(base_delay: Duration, max_delay: Duration) -> Self {
+        let (notify_sender, notify_receiver) = bounded(1);
+        Self {
+            state: Mutex::new(QueueState::default()),
+            notify_sender,
+            notify_receiver,
+            base_delay,
+            max_delay,
+        }
+    }
+
+    /// add key, if key is already queued this does nothing
+    pub async fn add(&self, key: K) {
+        trace!(?key, "add");
+        if self.state.lock().await.add(key) {
+            self.notify();
+        }
+    }
+
+    /// add key after delay. if key is already delayed, earliest deadline wins
+    pub async fn add_after(&self, key: K, delay: Duration) {
+        if delay.is_zero() {
+            return self.add(key).await;
+        }
+        trace!(?key, ?delay, "add after");
+        let deadline = Instant::now() + delay;
+        let mut state = self.state.lock().await;
+        let entry = state.delayed.entry(key).or_insert(deadline);
+        if deadline < *entry {
+            *entry = deadline;
+        }
+        drop(state);
+        self.notify();
+    }
+
+    /// add key after backoff delay. delay is doubled each time key is rate limited until [WorkQueue::forget] is called
+    pub async fn add_rate_limited(&self, key: K) {
+        let failures = {
+            let mut state = self.state.lock().await;
+            let failures = state.failures.entry(key.clone()).or_insert(0);
+            *failures += 1;
+            *failures
+        };
+        let delay = self.backoff(failures);
+        self.add_after(key, delay).await;
+    }
+
+    /// reset backoff for key
+    pub async fn forget(&self, key: &K) {
+        self.state.lock().await.failures.remove(key);
+    }
+
+    /// number of times key has been rate limited
+    pub async fn failures(&self, key: &K) -> u32 {
+        self.state
+            .lock()
+            .await
+            .failures
+            .get(key)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// wait for next key.  key must be released by [WorkQueue::done] once processed
+    pub async fn next(&self) -> K {
+        loop {
+            let deadline = {
+                let mut state = self.state.lock().await;
+                let deadline = state.promote(Instant::now());
+                if let Some(key) = state.queue.pop_front() {
+                    state.dirty.remove(&key);
+                    state.processing.insert(key.clone());
+                    return key;
+                }
+                deadline
+            };
+
+            match deadline {
+                Some(deadline) => {
+                    let delay = deadline.saturating_duration_since(Instant::now());
+                    let notified = Box::pin(self.notify_receiver.recv());
+                    if let Either::Right(_) = select(notified, Box::pin(sleep(delay))).await {
+                        trace!("delayed key is due");
+                    }
+                }
+                None => {
+                    let _ = self.notify_receiver.recv().await;
+                }
+            }
+        }
+    }
+
+    /// mark key as processed
+    pub async fn done(&self, key: &K) {
+        let mut state = self.state.lock().await;
+        state.processing.remove(key);
+        if state.dirty.contains(key) {
+            state.queue.push_back(key.clone());
+            drop(state);
+            self.notify();
+        }
+    }
+
+    /// number of keys ready to be processed
+    pub async fn len(&self) -> usize {
+        self.state.lock().await.queue.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    fn backoff(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    fn notify(&self) {
+        // only single wake up is needed
+        let _ = self.notify_sender.try_send(());
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::time::Duration;
+
+    use super::WorkQueue;
+
+    #[fluvio_future::test]
+    async fn test_queue_dedup() {
+        let queue: WorkQueue<String> = WorkQueue::default();
+        queue.add("a".to_owned()).await;
+        queue.add("a".to_owned()).await;
+        queue.add("b".to_owned()).await;
+        assert_eq!(queue.len().await, 2);
+
+        let key = queue.next().await;
+        assert_eq!(key, "a");
+
+        // while processing, key is not handed out again
+        queue.add("a".to_owned()).await;
+        assert_eq!(queue.next().await, "b");
+        assert!(queue.is_empty().await);
+
+        queue.done(&key).await;
+        assert_eq!(queue.next().await, "a");
+    }
+
+    #[fluvio_future::test]
+    async fn test_queue_delay() {
+        let queue: WorkQueue<String> = WorkQueue::default();
+        queue
+            .add_after("a".to_owned(), Duration::from_millis(50))
+            .await;
+        assert!(queue.is_empty().await);
+        assert_eq!(queue.next().await, "a");
+    }
+
+    #[test]
+    fn test_queue_backoff() {
+        let queue: WorkQueue<String> =
+            WorkQueue::new(Duration::from_millis(10), Duration::from_millis(50));
+        assert_eq!(queue.backoff(1), Duration::from_millis(10));
+        assert_eq!(queue.backoff(2), Duration::from_millis(20));
+        assert_eq!(queue.backoff(3), Duration::from_millis(40));
+        assert_eq!(queue.backoff(4), Duration::from_millis(50));
+        assert_eq!(queue.backoff(100), Duration::from_millis(50));
+    }
+}
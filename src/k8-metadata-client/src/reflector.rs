@@ -0,0 +1,304 @@
+//!
+//! # Reflector
+//!
+//! Keeps an in-memory copy of objects of a single kind in sync with the metadata service.
+//! It lists all objects, then watches for changes starting from the list's resource version.
+//! If the watch ends, it is re-established from the last seen resource version.
+//! If the resource version is too old (410 Gone), the objects are listed again.
+//!
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_channel::{bounded, Receiver, Sender};
+use async_lock::RwLock;
+use futures_util::future::{ready, FutureExt};
+use futures_util::stream::{select, BoxStream, StreamExt};
+use tracing::{debug, error, trace};
+
+use fluvio_future::retry::FibonacciBackoff;
+use fluvio_future::timer::sleep;
+use k8_types::{ItemMeta, K8Obj, K8Watch, Spec};
+
+use crate::{K8ErrorExt, MetadataClient, NameSpace, SharedClient};
+
+const GONE: u16 = 410;
+
+/// change observed by reflector, emitted after store has been updated
+#[derive(Debug, Clone)]
+pub enum ReflectorEvent<S>
+where
+    S: Spec,
+{
+    /// store was populated from full list. contains all objects in the store
+    Synced(Vec<K8Obj<S>>),
+    Added(K8Obj<S>),
+    Modified(K8Obj<S>),
+    Deleted(K8Obj<S>),
+}
+
+/// Read only view of objects maintained by reflector.
+/// Objects are keyed by namespace and name
+pub struct ReflectorStore<S>
+where
+    S: Spec,
+{
+    items: Arc<RwLock<HashMap<ItemMeta, K8Obj<S>>>>,
+    synced: Arc<AtomicBool>,
+    synced_signal: Receiver<()>,
+}
+
+impl<S> Clone for ReflectorStore<S>
+where
+    S: Spec,
+{
+    fn clone(&self) -> Self {
+        Self {
+            items: self.items.clone(),
+            synced: self.synced.clone(),
+            synced_signal: self.synced_signal.clone(),
+        }
+    }
+}
+
+impl<S> ReflectorStore<S>
+where
+    S: Spec,
+{
+    fn new(synced_signal: Receiver<()>) -> Self {
+        Self {
+            items: Arc::new(RwLock::new(HashMap::new())),
+            synced: Arc::new(AtomicBool::new(false)),
+            synced_signal,
+        }
+    }
+
+    /// get copy of object
+    pub async fn get(&self, key: &ItemMeta) -> Option<K8Obj<S>> {
+        self.items.read().await.get(key).cloned()
+    }
+
+    /// snapshot of all objects
+    pub async fn list(&self) -> Vec<K8Obj<S>> {
+        self.items.read().await.values().cloned().collect()
+    }
+
+    pub async fn len(&self) -> usize {
+        self.items.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.items.read().await.is_empty()
+    }
+
+    /// true if initial list has been loaded
+    pub fn has_synced(&self) -> bool {
+        self.synced.load(Ordering::SeqCst)
+    }
+
+    /// wait until initial list has been loaded
+    pub async fn wait_until_synced(&self) {
+        if self.has_synced() {
+            return;
+        }
+        // sender never sends, it only closes channel once synced
+        let _ = self.synced_signal.recv().await;
+    }
+
+    async fn replace(&self, objects: Vec<K8Obj<S>>) {
+        let mut items = self.items.write().await;
+        items.clear();
+        for obj in objects {
+            items.insert(obj.metadata.as_item(), obj);
+        }
+    }
+
+    async fn apply(&self, obj: K8Obj<S>) {
+        self.items.write().await.insert(obj.metadata.as_item(), obj);
+    }
+
+    async fn delete(&self, obj: &K8Obj<S>) {
+        self.items.write().await.remove(&obj.metadata.as_item());
+    }
+}
+
+/// Reflects objects of spec `S` into [ReflectorStore]
+///
+/// ```ignore
+/// let reflector = Reflector::<TopicSpec, _>::new(client, "default");
+/// let store = reflector.store();
+/// let mut events = reflector.into_stream();
+/// ```
+pub struct Reflector<S, C>
+where
+    S: Spec,
+{
+    client: SharedClient<C>,
+    namespace: NameSpace,
+    store: ReflectorStore<S>,
+    synced_sender: Sender<()>,
+    backoff: FibonacciBackoff,
+}
+
+impl<S, C> Reflector<S, C>
+where
+    S: Spec + 'static,
+    C: MetadataClient + 'static,
+{
+    pub fn new<N>(client: SharedClient<C>, namespace: N) -> Self
+    where
+        N: Into<NameSpace>,
+    {
+        let (synced_sender, synced_signal) = bounded(1);
+        Self {
+            client,
+            namespace: namespace.into(),
+            store: ReflectorStore::new(synced_signal),
+            synced_sender,
+            backoff: FibonacciBackoff::from_millis(100).max_delay(Duration::from_secs(30)),
+        }
+    }
+
+    /// backoff used when list or watch fails
+    pub fn with_backoff(mut self, backoff: FibonacciBackoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn store(&self) -> ReflectorStore<S> {
+        self.store.clone()
+    }
+
+    /// Stream of changes.  Store is only kept up to date while this stream is polled.
+    pub fn into_stream(self) -> BoxStream<'static, ReflectorEvent<S>> {
+        let (sender, receiver) = bounded(100);
+        let run = self.run(sender).into_stream().filter_map(|_| ready(None));
+        select(receiver, run).boxed()
+    }
+
+    async fn run(self, sender: Sender<ReflectorEvent<S>>) {
+        let label = S::label();
+        let mut backoff = self.backoff.clone();
+
+        'list: loop {
+            let list = match self
+                .client
+                .retrieve_items_with_option::<S, _>(self.namespace.clone(), None)
+                .await
+            {
+                Ok(list) => list,
+                Err(err) => {
+                    error!(label, %err, "list failed");
+                    Self::wait(&mut backoff).await;
+                    continue 'list;
+                }
+            };
+
+            let mut resource_version = list.metadata.resource_version;
+            debug!(label, %resource_version, items = list.items.len(), "listed");
+            self.store.replace(list.items.clone()).await;
+            self.store.synced.store(true, Ordering::SeqCst);
+            self.synced_sender.close();
+            if sender
+                .send(ReflectorEvent::Synced(list.items))
+                .await
+                .is_err()
+            {
+                debug!(label, "no more listener, terminating");
+                return;
+            }
+
+            loop {
+                let mut watch_stream = self.client.watch_stream_since::<S, _>(
+                    self.namespace.clone(),
+                    Some(resource_version.clone()),
+                );
+                let mut received = false;
+
+                while let Some(result) = watch_stream.next().await {
+                    let events = match result {
+                        Ok(events) => events,
+                        Err(err) if err.is_gone() => {
+                            debug!(label, "watch expired, re-listing");
+                            continue 'list;
+                        }
+                        Err(err) => {
+                            error!(label, %err, "watch failed");
+                            break;
+                        }
+                    };
+
+                    for event in events {
+                        let event = match event {
+                            Ok(K8Watch::ADDED(obj)) => {
+                                self.store.apply(obj.clone()).await;
+                                ReflectorEvent::Added(obj)
+                            }
+                            Ok(K8Watch::MODIFIED(obj)) => {
+                                self.store.apply(obj.clone()).await;
+                                ReflectorEvent::Modified(obj)
+                            }
+                            Ok(K8Watch::DELETED(obj)) => {
+                                self.store.delete(&obj).await;
+                                ReflectorEvent::Deleted(obj)
+                            }
+                            Ok(K8Watch::BOOKMARK(obj)) => {
+                                resource_version = obj.metadata.resource_version;
+                                continue;
+                            }
+                            Ok(K8Watch::ERROR(status)) if status.code == Some(GONE) => {
+                                debug!(label, "watch expired, re-listing");
+                                continue 'list;
+                            }
+                            Ok(K8Watch::ERROR(status)) => {
+                                error!(label, %status, "watch error");
+                                continue;
+                            }
+                            Err(err) if err.is_gone() => {
+                                debug!(label, "watch expired, re-listing");
+                                continue 'list;
+                            }
+                            Err(err) => {
+                                error!(label, %err, "invalid watch event");
+                                continue;
+                            }
+                        };
+
+                        received = true;
+                        if let Some(obj) = event_obj(&event) {
+                            resource_version = obj.metadata.resource_version.clone();
+                        }
+                        trace!(label, ?event, "reflected");
+                        if sender.send(event).await.is_err() {
+                            debug!(label, "no more listener, terminating");
+                            return;
+                        }
+                    }
+                }
+
+                if received {
+                    backoff = self.backoff.clone();
+                } else {
+                    Self::wait(&mut backoff).await;
+                }
+                debug!(label, %resource_version, "watch ended, resuming");
+            }
+        }
+    }
+
+    async fn wait(backoff: &mut FibonacciBackoff) {
+        if let Some(delay) = backoff.next() {
+            sleep(delay).await;
+        }
+    }
+}
+
+fn event_obj<S: Spec>(event: &ReflectorEvent<S>) -> Option<&K8Obj<S>> {
+    match event {
+        ReflectorEvent::Added(obj)
+        | ReflectorEvent::Modified(obj)
+        | ReflectorEvent::Deleted(obj) => Some(obj),
+        ReflectorEvent::Synced(_) => None,
+    }
+}
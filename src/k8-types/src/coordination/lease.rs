@@ -0,0 +1,42 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::{Crd, CrdNames, DefaultHeader, Spec, Status};
+
+const LEASE_API: Crd = Crd {
+    group: "coordination.k8s.io",
+    version: "v1",
+    names: CrdNames {
+        kind: "Lease",
+        plural: "leases",
+        singular: "lease",
+    },
+};
+
+/// Lease is used for leader election and node heartbeat.
+/// times are in MicroTime format, ex: `2024-01-02T15:04:05.000000Z`
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaseSpec {
+    pub holder_identity: Option<String>,
+    pub lease_duration_seconds: Option<i32>,
+    pub acquire_time: Option<String>,
+    pub renew_time: Option<String>,
+    pub lease_transitions: Option<i32>,
+}
+
+impl Spec for LeaseSpec {
+    type Status = LeaseStatus;
+    type Header = DefaultHeader;
+
+    fn metadata() -> &'static Crd {
+        &LEASE_API
+    }
+}
+
+/// lease doesn't have status
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaseStatus {}
+
+impl Status for LeaseStatus {}
@@ -46,17 +46,13 @@ pub struct PodSpec {
     pub node_selector: Option<HashMap<String, String>>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq, Default)]
 pub enum PodRestartPolicy {
-    Always,
+    #[default]
+    Always, // https://kubernetes.io/docs/concepts/workloads/pods/pod-lifecycle/#restart-policy
     Never,
     OnFailure,
 }
-impl Default for PodRestartPolicy {
-    fn default() -> Self {
-        Self::Always // https://kubernetes.io/docs/concepts/workloads/pods/pod-lifecycle/#restart-policy
-    }
-}
 
 #[derive(Deserialize, Serialize, Debug, Default, Clone, Eq, PartialEq)]
 #[serde(rename_all = "camelCase", default)]
@@ -93,19 +89,14 @@ pub struct ContainerSpec {
     pub liveness_probe: Option<Probe>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq, Default)]
 pub enum ImagePullPolicy {
-    Always,
+    #[default]
+    Always, // https://kubernetes.io/docs/concepts/containers/images/#updating-images
     Never,
     IfNotPresent,
 }
 
-impl Default for ImagePullPolicy {
-    fn default() -> Self {
-        Self::Always // https://kubernetes.io/docs/concepts/containers/images/#updating-images
-    }
-}
-
 #[derive(Deserialize, Serialize, Default, Debug, Clone, Eq, PartialEq)]
 #[serde(rename_all = "camelCase", default)]
 pub struct Probe {
@@ -0,0 +1,205 @@
+//!
+//! # Dynamic Objects
+//!
+//! Objects of resources which are only known at runtime, such as resources discovered
+//! from API server or read from user's manifest, without [Spec](crate::Spec) implementation.
+//!
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{Crd, ListMetadata, MetaStatus, ObjectMeta, Spec};
+
+/// group of core resources such as pods and services
+const CORE_GROUP: &str = "core";
+
+/// runtime descriptor of resource, counterpart of [Crd] for dynamic objects
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Eq, PartialEq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiResource {
+    /// group, empty or `core` for core resources
+    pub group: String,
+    pub version: String,
+    pub kind: String,
+    /// name of resource in URL, such as `deployments`
+    pub plural: String,
+    pub namespaced: bool,
+}
+
+impl ApiResource {
+    /// descriptor of resource with compile time spec
+    pub fn from_spec<S: Spec>() -> Self {
+        Self::from_crd(S::metadata(), S::NAME_SPACED)
+    }
+
+    pub fn from_crd(crd: &Crd, namespaced: bool) -> Self {
+        Self {
+            group: crd.group.to_owned(),
+            version: crd.version.to_owned(),
+            kind: crd.names.kind.to_owned(),
+            plural: crd.names.plural.to_owned(),
+            namespaced,
+        }
+    }
+
+    pub fn is_core(&self) -> bool {
+        self.group.is_empty() || self.group == CORE_GROUP
+    }
+
+    /// api version as in object, `v1` for core resources and `group/version` otherwise
+    pub fn api_version(&self) -> String {
+        if self.is_core() {
+            self.version.clone()
+        } else {
+            format!("{}/{}", self.group, self.version)
+        }
+    }
+}
+
+/// object of any resource, fields other than metadata are kept as JSON
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamicObject {
+    pub api_version: String,
+    pub kind: String,
+    #[serde(default)]
+    pub metadata: ObjectMeta,
+    /// remaining fields such as `spec`, `status` or `data`
+    #[serde(flatten)]
+    pub data: Value,
+}
+
+impl Default for DynamicObject {
+    fn default() -> Self {
+        Self {
+            api_version: String::default(),
+            kind: String::default(),
+            metadata: ObjectMeta::default(),
+            data: Value::Object(Map::new()),
+        }
+    }
+}
+
+impl DynamicObject {
+    /// new object of resource with name, fields can be set in data
+    pub fn new(name: impl Into<String>, resource: &ApiResource) -> Self {
+        Self {
+            api_version: resource.api_version(),
+            kind: resource.kind.clone(),
+            metadata: ObjectMeta {
+                name: name.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.metadata.namespace = namespace.into();
+        self
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// field of object such as `spec`
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.data.get(name)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamicList {
+    pub api_version: String,
+    pub kind: String,
+    pub metadata: ListMetadata,
+    #[serde(default)]
+    pub items: Vec<DynamicObject>,
+}
+
+/// watch event of dynamic object, same as [K8Watch](crate::K8Watch)
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", content = "object")]
+pub enum DynamicWatch {
+    ADDED(DynamicObject),
+    MODIFIED(DynamicObject),
+    DELETED(DynamicObject),
+    BOOKMARK(DynamicObject),
+    ERROR(MetaStatus),
+}
+
+#[cfg(test)]
+mod test {
+
+    use serde_json::json;
+
+    use super::{ApiResource, DynamicObject, DynamicWatch};
+
+    fn deployments() -> ApiResource {
+        ApiResource {
+            group: "apps".to_owned(),
+            version: "v1".to_owned(),
+            kind: "Deployment".to_owned(),
+            plural: "deployments".to_owned(),
+            namespaced: true,
+        }
+    }
+
+    #[test]
+    fn test_api_version() {
+        assert_eq!(deployments().api_version(), "apps/v1");
+        let config_maps = ApiResource {
+            group: String::new(),
+            version: "v1".to_owned(),
+            kind: "ConfigMap".to_owned(),
+            plural: "configmaps".to_owned(),
+            namespaced: true,
+        };
+        assert_eq!(config_maps.api_version(), "v1");
+    }
+
+    #[test]
+    fn test_dynamic_object_round_trip() {
+        let value = json!({
+            "apiVersion": "apps/v1",
+            "kind": "Deployment",
+            "metadata": {"name": "web", "namespace": "default", "labels": {"app": "web"}},
+            "spec": {"replicas": 3}
+        });
+        let obj: DynamicObject = serde_json::from_value(value.clone()).expect("object");
+        assert_eq!(obj.metadata.name, "web");
+        assert_eq!(obj.metadata.labels["app"], "web");
+        assert_eq!(obj.field("spec"), Some(&json!({"replicas": 3})));
+        assert!(obj.data.get("metadata").is_none());
+
+        let obj = DynamicObject::new("web", &deployments())
+            .with_namespace("default")
+            .with_data(json!({"spec": {"replicas": 3}}));
+        let serialized = serde_json::to_value(&obj).expect("json");
+        assert_eq!(serialized["apiVersion"], "apps/v1");
+        assert_eq!(serialized["spec"]["replicas"], 3);
+        assert_eq!(serialized["metadata"]["namespace"], "default");
+
+        let empty = serde_json::to_value(DynamicObject::default()).expect("json");
+        assert_eq!(empty["kind"], "");
+    }
+
+    #[test]
+    fn test_dynamic_watch() {
+        let event: DynamicWatch = serde_json::from_value(json!({
+            "type": "ADDED",
+            "object": {"apiVersion": "v1", "kind": "ConfigMap", "metadata": {"name": "config"}, "data": {"key": "value"}}
+        }))
+        .expect("event");
+        let DynamicWatch::ADDED(obj) = event else {
+            panic!("expected added");
+        };
+        assert_eq!(
+            obj.field("data").and_then(|data| data.get("key")),
+            Some(&json!("value"))
+        );
+    }
+}
@@ -1,6 +1,8 @@
 mod crd;
+mod dynamic;
 mod int_or_string;
 mod metadata;
+mod selector;
 pub mod options;
 pub mod store;
 #[cfg(feature = "core")]
@@ -11,9 +13,13 @@ pub mod app;
 pub mod storage;
 #[cfg(feature = "batch")]
 pub mod batch;
+#[cfg(feature = "coordination")]
+pub mod coordination;
 
 pub use self::crd::*;
+pub use self::dynamic::*;
 pub use self::metadata::*;
+pub use self::selector::*;
 pub use self::spec_def::*;
 
 mod spec_def {
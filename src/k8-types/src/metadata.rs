@@ -7,7 +7,7 @@ use serde::de::{DeserializeOwned, Deserializer};
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::Spec;
+use crate::{LabelSelectorRequirement, Selector, Spec};
 
 pub const DEFAULT_NS: &str = "default";
 pub const TYPE_OPAQUE: &str = "Opaque";
@@ -232,13 +232,34 @@ impl From<ObjectMeta> for InputObjectMeta {
 }
 
 /// used for retrieving,updating and deleting item
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Eq, PartialEq, Hash)]
 #[serde(rename_all = "camelCase")]
 pub struct ItemMeta {
     pub name: String,
     pub namespace: String,
 }
 
+/// `namespace/name`, or just name for cluster scoped object
+impl fmt::Display for ItemMeta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.namespace.is_empty() {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{}/{}", self.namespace, self.name)
+        }
+    }
+}
+
+impl K8Meta for ItemMeta {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+}
+
 impl From<ObjectMeta> for ItemMeta {
     fn from(meta: ObjectMeta) -> Self {
         Self {
@@ -319,7 +340,7 @@ where
     ForegroundDelete(K8Obj<S>),
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct MetaStatus {
     pub api_version: String,
@@ -350,7 +371,7 @@ impl std::error::Error for MetaStatus {}
 
 /// Default status implementation
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Deserialize, Debug, Eq, PartialEq, Clone)]
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
 pub enum StatusEnum {
     #[serde(rename = "Success")]
     SUCCESS,
@@ -372,12 +393,24 @@ impl Display for StatusEnum {
     pub status: StatusEnum,
 */
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
 pub struct StatusDetails {
+    #[serde(default)]
     pub name: String,
     pub group: Option<String>,
+    #[serde(default)]
     pub kind: String,
     pub uid: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub causes: Vec<StatusCause>,
+}
+
+/// cause of error, such as field which failed validation or conflicted
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Eq, PartialEq)]
+pub struct StatusCause {
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    pub field: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Default, Clone)]
@@ -630,6 +663,7 @@ where
             metadata: ListMetadata {
                 _continue: None,
                 resource_version: S::api_version(),
+                remaining_item_count: None,
             },
         }
     }
@@ -662,6 +696,10 @@ where
     ADDED(K8Obj<S>),
     MODIFIED(K8Obj<S>),
     DELETED(K8Obj<S>),
+    /// only resource version of object is set, sent when `allowWatchBookmarks` is requested
+    BOOKMARK(K8Obj<S>),
+    /// error from server, such as expired resource version
+    ERROR(MetaStatus),
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -670,12 +708,18 @@ pub struct ListMetadata {
     pub _continue: Option<String>,
     #[serde(default)]
     pub resource_version: String,
+    /// number of items not yet returned, only set when list is chunked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_item_count: Option<i64>,
 }
 
 #[derive(Deserialize, Serialize, Default, Debug, Eq, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LabelSelector {
+    #[serde(default)]
     pub match_labels: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub match_expressions: Vec<LabelSelectorRequirement>,
 }
 
 impl LabelSelector {
@@ -684,7 +728,20 @@ impl LabelSelector {
         for (key, value) in labels {
             match_labels.insert(key.into(), value.into());
         }
-        LabelSelector { match_labels }
+        LabelSelector {
+            match_labels,
+            match_expressions: vec![],
+        }
+    }
+
+    pub fn with_expression(mut self, expression: LabelSelectorRequirement) -> Self {
+        self.match_expressions.push(expression);
+        self
+    }
+
+    /// evaluate selector against labels of object
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        Selector::from(self).matches(labels)
     }
 }
 
@@ -752,6 +809,7 @@ pub struct ResourceFieldSelector {
 mod test {
 
     use super::Env;
+    use super::ItemMeta;
     use super::ObjectMeta;
 
     #[test]
@@ -770,6 +828,78 @@ mod test {
         assert_eq!(env.name, "lang");
         assert_eq!(env.value, Some("english".to_owned()));
     }
+
+    #[test]
+    fn test_item_meta_display() {
+        let item = ItemMeta {
+            name: "test".to_owned(),
+            namespace: "default".to_owned(),
+        };
+        assert_eq!(item.to_string(), "default/test");
+
+        let cluster_item = ItemMeta {
+            name: "test".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(cluster_item.to_string(), "test");
+    }
+}
+
+#[cfg(test)]
+mod test_watch {
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::{Crd, CrdNames, DefaultHeader, Spec, Status};
+    use super::K8Watch;
+
+    #[derive(Deserialize, Serialize, Default, Debug, Clone)]
+    struct TestSpec {
+        value: i32,
+    }
+
+    impl Spec for TestSpec {
+        type Status = TestStatus;
+        type Header = DefaultHeader;
+
+        fn metadata() -> &'static Crd {
+            &Crd {
+                group: "test",
+                version: "v1",
+                names: CrdNames {
+                    kind: "Test",
+                    plural: "tests",
+                    singular: "test",
+                },
+            }
+        }
+    }
+
+    #[derive(Deserialize, Serialize, Default, Debug, Clone)]
+    struct TestStatus {}
+
+    impl Status for TestStatus {}
+
+    #[test]
+    fn test_watch_bookmark() {
+        let data = r#"{"type":"BOOKMARK","object":{"kind":"Test","apiVersion":"test/v1","metadata":{"resourceVersion":"12746"}}}"#;
+        let watch: K8Watch<TestSpec> = serde_json::from_str(data).expect("bookmark");
+        let K8Watch::BOOKMARK(obj) = watch else {
+            panic!("expected bookmark, got: {:#?}", watch);
+        };
+        assert_eq!(obj.metadata.resource_version, "12746");
+    }
+
+    #[test]
+    fn test_watch_error() {
+        let data = r#"{"type":"ERROR","object":{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"too old resource version: 1 (12746)","reason":"Expired","code":410}}"#;
+        let watch: K8Watch<TestSpec> = serde_json::from_str(data).expect("error");
+        let K8Watch::ERROR(status) = watch else {
+            panic!("expected error, got: {:#?}", watch);
+        };
+        assert_eq!(status.code, Some(410));
+        assert_eq!(status.reason.as_deref(), Some("Expired"));
+    }
 }
 
 /*
@@ -1,4 +1,14 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+/// how `resourceVersion` of list request is interpreted, see
+/// <https://kubernetes.io/docs/reference/using-api/api-concepts/#semantics-for-get-and-list>
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ResourceVersionMatch {
+    /// data at least as new as resource version
+    NotOlderThan,
+    /// data at exact resource version
+    Exact,
+}
 
 /// goes as query parameter
 #[derive(Serialize, Default, Debug)]
@@ -8,12 +18,13 @@ pub struct ListOptions {
     #[serde(rename = "continue")]
     pub continu: Option<String>,
     pub field_selector: Option<String>,
-    pub include_uninitialized: Option<bool>,
     pub label_selector: Option<String>,
     pub limit: Option<u32>,
     pub resource_version: Option<String>,
+    pub resource_version_match: Option<ResourceVersionMatch>,
     pub timeout_seconds: Option<u32>,
     pub watch: Option<bool>,
+    pub allow_watch_bookmarks: Option<bool>,
 }
 
 #[derive(Serialize, Debug)]
@@ -59,7 +70,7 @@ pub struct Precondition {
 #[cfg(test)]
 mod test {
 
-    use super::ListOptions;
+    use super::{ListOptions, ResourceVersionMatch};
 
     #[test]
     fn test_list_query() {
@@ -72,4 +83,32 @@ mod test {
         let qs = serde_qs::to_string(&opt).unwrap();
         assert_eq!(qs, "pretty=true&watch=true")
     }
+
+    #[test]
+    fn test_watch_bookmarks_query() {
+        let opt = ListOptions {
+            watch: Some(true),
+            allow_watch_bookmarks: Some(true),
+            ..Default::default()
+        };
+
+        let qs = serde_qs::to_string(&opt).unwrap();
+        assert_eq!(qs, "watch=true&allowWatchBookmarks=true")
+    }
+
+    #[test]
+    fn test_resource_version_match_query() {
+        let opt = ListOptions {
+            limit: Some(10),
+            resource_version: Some("100".to_owned()),
+            resource_version_match: Some(ResourceVersionMatch::NotOlderThan),
+            ..Default::default()
+        };
+
+        let qs = serde_qs::to_string(&opt).unwrap();
+        assert_eq!(
+            qs,
+            "limit=10&resourceVersion=100&resourceVersionMatch=NotOlderThan"
+        )
+    }
 }
@@ -0,0 +1,580 @@
+//!
+//! # Selectors
+//!
+//! Typed label selectors, such as `app=web,tier in (frontend,backend),!canary`,
+//! and field selectors, such as `metadata.name=web,status.phase!=Running`.
+//! Selectors render to `labelSelector` and `fieldSelector` query parameters of list and
+//! watch requests and can also be evaluated locally against objects.
+//!
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{K8Obj, LabelSelector, Spec};
+
+/// operator of [LabelSelectorRequirement]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LabelSelectorOperator {
+    In,
+    NotIn,
+    Exists,
+    DoesNotExist,
+}
+
+/// entry of `matchExpressions` in [LabelSelector]
+#[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LabelSelectorRequirement {
+    pub key: String,
+    pub operator: LabelSelectorOperator,
+    /// must be empty for `Exists` and `DoesNotExist`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+impl LabelSelectorRequirement {
+    pub fn new<K, I, T>(key: K, operator: LabelSelectorOperator, values: I) -> Self
+    where
+        K: Into<String>,
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        Self {
+            key: key.into(),
+            operator,
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// selector expression which can't be parsed
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseSelectorError(String);
+
+impl Display for ParseSelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid selector: {}", self.0)
+    }
+}
+
+impl Error for ParseSelectorError {}
+
+/// single term of [Selector]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Requirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    In(String, Vec<String>),
+    NotIn(String, Vec<String>),
+    Exists(String),
+    NotExists(String),
+}
+
+impl Requirement {
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Equals(key, _)
+            | Self::NotEquals(key, _)
+            | Self::In(key, _)
+            | Self::NotIn(key, _)
+            | Self::Exists(key)
+            | Self::NotExists(key) => key,
+        }
+    }
+
+    /// check value of key, None if key is not present
+    pub fn matches(&self, value: Option<&str>) -> bool {
+        match self {
+            Self::Equals(_, expected) => value == Some(expected.as_str()),
+            Self::NotEquals(_, expected) => value != Some(expected.as_str()),
+            Self::In(_, values) => value.is_some_and(|found| values.iter().any(|v| v == found)),
+            Self::NotIn(_, values) => !value.is_some_and(|found| values.iter().any(|v| v == found)),
+            Self::Exists(_) => value.is_some(),
+            Self::NotExists(_) => value.is_none(),
+        }
+    }
+}
+
+impl Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Equals(key, value) => write!(f, "{key}={value}"),
+            Self::NotEquals(key, value) => write!(f, "{key}!={value}"),
+            Self::In(key, values) => write!(f, "{key} in ({})", values.join(",")),
+            Self::NotIn(key, values) => write!(f, "{key} notin ({})", values.join(",")),
+            Self::Exists(key) => write!(f, "{key}"),
+            Self::NotExists(key) => write!(f, "!{key}"),
+        }
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '=' | '!' | '(' | ')' | ','))
+}
+
+fn is_valid_value(value: &str) -> bool {
+    !value
+        .chars()
+        .any(|c| c.is_whitespace() || matches!(c, '=' | '!' | '(' | ')' | ','))
+}
+
+impl FromStr for Requirement {
+    type Err = ParseSelectorError;
+
+    fn from_str(term: &str) -> Result<Self, Self::Err> {
+        let term = term.trim();
+        let invalid = || ParseSelectorError(term.to_owned());
+
+        let requirement = if let Some(key) = term.strip_prefix('!') {
+            Self::NotExists(key.trim().to_owned())
+        } else if let Some((left, values)) = term.split_once('(') {
+            let values = values.strip_suffix(')').ok_or_else(invalid)?;
+            // set of values can't be empty
+            if values.trim().is_empty() {
+                return Err(invalid());
+            }
+            let values: Vec<String> = values
+                .split(',')
+                .map(|value| value.trim().to_owned())
+                .collect();
+            if values.iter().any(|value| !is_valid_value(value)) {
+                return Err(invalid());
+            }
+            let mut parts = left.split_whitespace();
+            let key = parts.next().ok_or_else(invalid)?.to_owned();
+            match (parts.next(), parts.next()) {
+                (Some("in"), None) => Self::In(key, values),
+                (Some("notin"), None) => Self::NotIn(key, values),
+                _ => return Err(invalid()),
+            }
+        } else if let Some((key, value)) = term.split_once("!=") {
+            Self::NotEquals(key.trim().to_owned(), value.trim().to_owned())
+        } else if let Some((key, value)) = term.split_once('=') {
+            let value = value.strip_prefix('=').unwrap_or(value);
+            Self::Equals(key.trim().to_owned(), value.trim().to_owned())
+        } else {
+            Self::Exists(term.to_owned())
+        };
+
+        let value_valid = match &requirement {
+            Self::Equals(_, value) | Self::NotEquals(_, value) => is_valid_value(value),
+            _ => true,
+        };
+        if is_valid_key(requirement.key()) && value_valid {
+            Ok(requirement)
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl From<&LabelSelectorRequirement> for Requirement {
+    fn from(expression: &LabelSelectorRequirement) -> Self {
+        let key = expression.key.clone();
+        match expression.operator {
+            LabelSelectorOperator::In => Self::In(key, expression.values.clone()),
+            LabelSelectorOperator::NotIn => Self::NotIn(key, expression.values.clone()),
+            LabelSelectorOperator::Exists => Self::Exists(key),
+            LabelSelectorOperator::DoesNotExist => Self::NotExists(key),
+        }
+    }
+}
+
+/// split selector by commas which are not inside parenthesis
+fn split_terms(selector: &str) -> Vec<&str> {
+    let mut terms = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in selector.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                terms.push(&selector[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    terms.push(&selector[start..]);
+    terms
+        .into_iter()
+        .filter(|term| !term.trim().is_empty())
+        .collect()
+}
+
+/// label selector, all requirements must match. empty selector matches everything.
+///
+/// ```
+/// use k8_types::Selector;
+///
+/// let selector = Selector::new()
+///     .equals("app", "web")
+///     .is_in("tier", ["frontend", "backend"])
+///     .not_exists("canary");
+/// assert_eq!(selector.to_string(), "app=web,tier in (frontend,backend),!canary");
+/// assert_eq!(selector, "app=web, tier in (frontend, backend), !canary".parse().unwrap());
+/// ```
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Selector {
+    requirements: Vec<Requirement>,
+}
+
+impl Selector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_requirement(mut self, requirement: Requirement) -> Self {
+        self.requirements.push(requirement);
+        self
+    }
+
+    pub fn equals(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.with_requirement(Requirement::Equals(key.into(), value.into()))
+    }
+
+    pub fn not_equals(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.with_requirement(Requirement::NotEquals(key.into(), value.into()))
+    }
+
+    pub fn is_in<I, T>(self, key: impl Into<String>, values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let values = values.into_iter().map(Into::into).collect();
+        self.with_requirement(Requirement::In(key.into(), values))
+    }
+
+    pub fn not_in<I, T>(self, key: impl Into<String>, values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let values = values.into_iter().map(Into::into).collect();
+        self.with_requirement(Requirement::NotIn(key.into(), values))
+    }
+
+    pub fn exists(self, key: impl Into<String>) -> Self {
+        self.with_requirement(Requirement::Exists(key.into()))
+    }
+
+    pub fn not_exists(self, key: impl Into<String>) -> Self {
+        self.with_requirement(Requirement::NotExists(key.into()))
+    }
+
+    pub fn requirements(&self) -> &[Requirement] {
+        &self.requirements
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requirements.is_empty()
+    }
+
+    /// evaluate selector against labels, such as [ObjectMeta::labels](crate::ObjectMeta::labels)
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.requirements.iter().all(|requirement| {
+            requirement.matches(labels.get(requirement.key()).map(String::as_str))
+        })
+    }
+}
+
+impl Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, requirement) in self.requirements.iter().enumerate() {
+            if index > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{requirement}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Selector {
+    type Err = ParseSelectorError;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        let requirements = split_terms(selector)
+            .into_iter()
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { requirements })
+    }
+}
+
+impl From<&LabelSelector> for Selector {
+    fn from(label_selector: &LabelSelector) -> Self {
+        let mut labels: Vec<_> = label_selector.match_labels.iter().collect();
+        labels.sort();
+        let requirements = labels
+            .into_iter()
+            .map(|(key, value)| Requirement::Equals(key.clone(), value.clone()))
+            .chain(label_selector.match_expressions.iter().map(Into::into))
+            .collect();
+        Self { requirements }
+    }
+}
+
+/// field selector, all requirements must match. only equality and inequality are supported.
+/// fields are JSON paths of object, such as `spec.nodeName`. missing field has empty value.
+///
+/// ```
+/// use k8_types::FieldSelector;
+///
+/// let selector = FieldSelector::new()
+///     .name("web")
+///     .not_equals("status.phase", "Running");
+/// assert_eq!(selector.to_string(), "metadata.name=web,status.phase!=Running");
+/// ```
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct FieldSelector {
+    requirements: Vec<Requirement>,
+}
+
+impl FieldSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn equals(mut self, field: impl Into<String>, value: impl Into<String>) -> Self {
+        self.requirements
+            .push(Requirement::Equals(field.into(), value.into()));
+        self
+    }
+
+    pub fn not_equals(mut self, field: impl Into<String>, value: impl Into<String>) -> Self {
+        self.requirements
+            .push(Requirement::NotEquals(field.into(), value.into()));
+        self
+    }
+
+    /// `metadata.name=`
+    pub fn name(self, name: impl Into<String>) -> Self {
+        self.equals("metadata.name", name)
+    }
+
+    /// `metadata.namespace=`
+    pub fn namespace(self, namespace: impl Into<String>) -> Self {
+        self.equals("metadata.namespace", namespace)
+    }
+
+    pub fn requirements(&self) -> &[Requirement] {
+        &self.requirements
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requirements.is_empty()
+    }
+
+    /// evaluate selector against object
+    pub fn matches<S: Spec>(&self, obj: &K8Obj<S>) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        serde_json::to_value(obj)
+            .map(|value| self.matches_value(&value))
+            .unwrap_or(false)
+    }
+
+    /// evaluate selector against object serialized to JSON, such as [DynamicObject](crate::DynamicObject)
+    pub fn matches_value(&self, obj: &Value) -> bool {
+        self.requirements.iter().all(|requirement| {
+            let value = requirement
+                .key()
+                .split('.')
+                .try_fold(obj, |value, field| value.get(field));
+            let value = match value {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+            };
+            requirement.matches(Some(&value))
+        })
+    }
+}
+
+impl Display for FieldSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, requirement) in self.requirements.iter().enumerate() {
+            if index > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{requirement}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for FieldSelector {
+    type Err = ParseSelectorError;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        let requirements = split_terms(selector)
+            .into_iter()
+            .map(|term| match term.parse()? {
+                requirement @ (Requirement::Equals(..) | Requirement::NotEquals(..)) => {
+                    Ok(requirement)
+                }
+                _ => Err(ParseSelectorError(term.trim().to_owned())),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { requirements })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use crate::LabelSelector;
+
+    use super::{FieldSelector, LabelSelectorOperator, LabelSelectorRequirement, Requirement, Selector};
+
+    fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        let selector: Selector =
+            "app==web, tier in (frontend, backend),env notin(prod),canary,!debug,version!=1"
+                .parse()
+                .expect("parse");
+        assert_eq!(
+            selector.requirements(),
+            &[
+                Requirement::Equals("app".to_owned(), "web".to_owned()),
+                Requirement::In(
+                    "tier".to_owned(),
+                    vec!["frontend".to_owned(), "backend".to_owned()]
+                ),
+                Requirement::NotIn("env".to_owned(), vec!["prod".to_owned()]),
+                Requirement::Exists("canary".to_owned()),
+                Requirement::NotExists("debug".to_owned()),
+                Requirement::NotEquals("version".to_owned(), "1".to_owned()),
+            ]
+        );
+        assert_eq!(
+            selector.to_string(),
+            "app=web,tier in (frontend,backend),env notin (prod),canary,!debug,version!=1"
+        );
+        assert_eq!(selector.to_string().parse::<Selector>(), Ok(selector));
+
+        assert!("".parse::<Selector>().expect("empty").is_empty());
+        assert!("app in web".parse::<Selector>().is_err());
+        assert!("app within (web)".parse::<Selector>().is_err());
+        assert!("app in ()".parse::<Selector>().is_err());
+        assert!("app notin ( )".parse::<Selector>().is_err());
+        assert!("=web".parse::<Selector>().is_err());
+        assert!("app=web server".parse::<Selector>().is_err());
+    }
+
+    #[test]
+    fn test_selector_matches() {
+        let web = labels(&[("app", "web"), ("tier", "frontend")]);
+        let db = labels(&[("app", "db")]);
+
+        let selector = Selector::new().equals("app", "web");
+        assert!(selector.matches(&web));
+        assert!(!selector.matches(&db));
+
+        let selector = Selector::new()
+            .is_in("app", ["web", "db"])
+            .not_exists("tier");
+        assert!(!selector.matches(&web));
+        assert!(selector.matches(&db));
+
+        let selector = Selector::new().not_in("app", ["db"]).exists("tier");
+        assert!(selector.matches(&web));
+        assert!(!selector.matches(&db));
+
+        let selector = Selector::new().not_equals("tier", "frontend");
+        assert!(!selector.matches(&web));
+        assert!(selector.matches(&db));
+
+        assert!(Selector::new().matches(&db));
+    }
+
+    #[test]
+    fn test_label_selector_expressions() {
+        let label_selector: LabelSelector = serde_json::from_value(serde_json::json!({
+            "matchLabels": {"app": "web"},
+            "matchExpressions": [
+                {"key": "tier", "operator": "In", "values": ["frontend"]},
+                {"key": "canary", "operator": "DoesNotExist"}
+            ]
+        }))
+        .expect("selector");
+        assert_eq!(
+            label_selector.match_expressions[1],
+            LabelSelectorRequirement::new(
+                "canary",
+                LabelSelectorOperator::DoesNotExist,
+                Vec::<String>::new()
+            )
+        );
+        assert_eq!(
+            Selector::from(&label_selector).to_string(),
+            "app=web,tier in (frontend),!canary"
+        );
+        assert!(label_selector.matches(&labels(&[("app", "web"), ("tier", "frontend")])));
+        assert!(!label_selector.matches(&labels(&[
+            ("app", "web"),
+            ("tier", "frontend"),
+            ("canary", "true")
+        ])));
+
+        let serialized =
+            serde_json::to_value(LabelSelector::new_labels(vec![("app", "web")])).expect("json");
+        assert!(serialized.get("matchExpressions").is_none());
+    }
+
+    #[test]
+    fn test_field_selector() {
+        let selector: FieldSelector = "metadata.name==web, status.phase!=Running,spec.nodeName="
+            .parse()
+            .expect("parse");
+        assert_eq!(
+            selector.to_string(),
+            "metadata.name=web,status.phase!=Running,spec.nodeName="
+        );
+        assert!("metadata.name in (web)".parse::<FieldSelector>().is_err());
+        assert!("spec.nodeName".parse::<FieldSelector>().is_err());
+
+        let pending = json!({
+            "metadata": {"name": "web", "namespace": "default"},
+            "spec": {"replicas": 1},
+            "status": {"phase": "Pending"}
+        });
+        assert!(selector.matches_value(&pending));
+        assert!(!selector.matches_value(&json!({
+            "metadata": {"name": "web"},
+            "status": {"phase": "Running"}
+        })));
+        assert!(!selector.matches_value(&json!({
+            "metadata": {"name": "web"},
+            "spec": {"nodeName": "node1"}
+        })));
+
+        let selector = FieldSelector::new()
+            .namespace("default")
+            .equals("spec.replicas", "1");
+        assert!(selector.matches_value(&pending));
+        assert!(!FieldSelector::new().name("db").matches_value(&pending));
+    }
+}